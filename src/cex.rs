@@ -1,15 +1,35 @@
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
 use reqwest::blocking::Client as HttpClient;
 use serde::Deserialize;
 use serde_json::Value;
 
+use crate::config::Config;
+use crate::log_err;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const QUOTE_STALE_AFTER: Duration = Duration::from_secs(10);
+
+static VENUE_HEALTH: Mutex<BTreeMap<String, VenueHealth>> = Mutex::new(BTreeMap::new());
+static REFRESHED: RwLock<Option<(Instant, HashMap<String, AssetReference>)>> = RwLock::new(None);
+
+#[derive(Debug, Default)]
+struct VenueHealth {
+    consecutive_failures: usize,
+    disabled_until: Option<Instant>,
+}
+
 #[derive(Debug, Clone)]
 pub struct VenueQuote {
     pub venue: String,
     pub mid: f64,
+    pub spread_pct: f64,
+    pub observed_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,80 +37,313 @@ pub struct AssetReference {
     pub asset: String,
     pub reference_price: f64,
     pub quotes: Vec<VenueQuote>,
+    pub confidence: f64,
 }
 
-pub fn scan_btc_eth_references(min_sources: usize) -> Result<HashMap<String, AssetReference>> {
-    let http = HttpClient::builder()
-        .timeout(Duration::from_secs(3))
-        .build()
-        .context("failed to build cex http client")?;
-
+pub fn scan_btc_eth_references(
+    config: &Config,
+    http: &HttpClient,
+    assets: &[&str],
+) -> Result<HashMap<String, AssetReference>> {
     let mut out = HashMap::new();
 
-    if let Some(reference) = build_reference(
-        "BTC",
-        vec![
-            fetch_coinbase_mid(&http, "BTC-USD"),
-            fetch_kraken_mid(&http, "XBTUSD"),
-            fetch_binance_mid(&http, "BTCUSDT"),
-        ],
-        min_sources,
-    ) {
-        out.insert("BTC".to_string(), reference);
-    }
-
-    if let Some(reference) = build_reference(
-        "ETH",
-        vec![
-            fetch_coinbase_mid(&http, "ETH-USD"),
-            fetch_kraken_mid(&http, "ETHUSD"),
-            fetch_binance_mid(&http, "ETHUSDT"),
-        ],
-        min_sources,
-    ) {
-        out.insert("ETH".to_string(), reference);
+    if assets.contains(&"BTC") {
+        if let Some(reference) = build_reference(
+            "BTC",
+            vec![
+                tracked(config, "coinbase", || {
+                    fetch_coinbase_mid(http, symbol(config, "BTC", "coinbase", "BTC-USD"))
+                }),
+                tracked(config, "kraken", || {
+                    fetch_kraken_mid(http, symbol(config, "BTC", "kraken", "XBTUSD"))
+                }),
+                tracked(config, "binance", || {
+                    fetch_binance_mid(http, symbol(config, "BTC", "binance", "BTCUSDT"))
+                }),
+            ],
+            config,
+        ) {
+            out.insert("BTC".to_string(), reference);
+        }
+    }
+
+    if assets.contains(&"ETH") {
+        if let Some(reference) = build_reference(
+            "ETH",
+            vec![
+                tracked(config, "coinbase", || {
+                    fetch_coinbase_mid(http, symbol(config, "ETH", "coinbase", "ETH-USD"))
+                }),
+                tracked(config, "kraken", || {
+                    fetch_kraken_mid(http, symbol(config, "ETH", "kraken", "ETHUSD"))
+                }),
+                tracked(config, "binance", || {
+                    fetch_binance_mid(http, symbol(config, "ETH", "binance", "ETHUSDT"))
+                }),
+            ],
+            config,
+        ) {
+            out.insert("ETH".to_string(), reference);
+        }
     }
 
     Ok(out)
 }
 
+fn symbol<'a>(config: &'a Config, asset: &str, venue: &str, default: &'a str) -> &'a str {
+    config
+        .cex_symbols
+        .get(asset)
+        .and_then(|venues| venues.get(venue))
+        .map(String::as_str)
+        .unwrap_or(default)
+}
+
+pub fn spawn_cex_refresher(
+    config: Config,
+    http: HttpClient,
+    interval_ms: u64,
+    assets: Vec<&'static str>,
+) {
+    thread::spawn(move || loop {
+        match scan_btc_eth_references(&config, &http, &assets) {
+            Ok(map) => {
+                let mut shared = REFRESHED.write().unwrap_or_else(|e| e.into_inner());
+                *shared = Some((Instant::now(), map));
+            }
+            Err(err) => log_err!("CEX background refresh failed: {}", err),
+        }
+        thread::sleep(Duration::from_millis(interval_ms));
+    });
+}
+
+pub fn refreshed_references(
+    config: &Config,
+    assets: &[&str],
+) -> Option<HashMap<String, AssetReference>> {
+    if config.cex_refresh_ms == 0 {
+        return None;
+    }
+    let shared = REFRESHED.read().unwrap_or_else(|e| e.into_inner());
+    let (fetched_at, map) = shared.as_ref()?;
+    let age = fetched_at.elapsed();
+    if age > Duration::from_millis(config.cex_refresh_ms.saturating_mul(3)) {
+        log_err!(
+            "Background CEX references are stale ({}ms old); scanning on demand.",
+            age.as_millis()
+        );
+        return None;
+    }
+    log_err!(
+        "Using background CEX references ({}ms old).",
+        age.as_millis()
+    );
+    Some(
+        map.iter()
+            .filter(|(asset, _)| assets.contains(&asset.as_str()))
+            .map(|(asset, reference)| (asset.clone(), reference.clone()))
+            .collect(),
+    )
+}
+
+fn tracked<F>(config: &Config, venue: &str, fetch: F) -> Result<VenueQuote>
+where
+    F: FnOnce() -> Result<VenueQuote>,
+{
+    if config.cex_venue_failure_limit == 0 {
+        return fetch();
+    }
+
+    let now = Instant::now();
+    {
+        let health = VENUE_HEALTH.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(until) = health.get(venue).and_then(|h| h.disabled_until) {
+            if now < until {
+                return Err(anyhow!("{} disabled after repeated failures", venue));
+            }
+        }
+    }
+
+    let result = fetch();
+    let mut health = VENUE_HEALTH.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = health.entry(venue.to_string()).or_default();
+    match &result {
+        Ok(_) => {
+            if entry.disabled_until.take().is_some() {
+                log_err!("CEX venue {} re-enabled", venue);
+            }
+            entry.consecutive_failures = 0;
+        }
+        Err(err) => {
+            entry.consecutive_failures += 1;
+            log_err!(
+                "CEX venue {} failed ({} in a row): {}",
+                venue,
+                entry.consecutive_failures,
+                err
+            );
+            if entry.consecutive_failures >= config.cex_venue_failure_limit {
+                entry.disabled_until =
+                    Some(now + Duration::from_secs(config.cex_venue_cooldown_secs));
+                log_err!(
+                    "CEX venue {} disabled for {}s",
+                    venue,
+                    config.cex_venue_cooldown_secs
+                );
+            }
+        }
+    }
+    result
+}
+
 fn build_reference(
     asset: &str,
     results: Vec<Result<VenueQuote>>,
-    min_sources: usize,
+    config: &Config,
 ) -> Option<AssetReference> {
+    let venues = results.len();
     let quotes = results
         .into_iter()
         .filter_map(Result::ok)
         .filter(|q| q.mid.is_finite() && q.mid > 0.0)
+        .filter(|q| {
+            if q.spread_pct <= config.cex_max_spread_pct {
+                return true;
+            }
+            log_err!(
+                "CEX {} quote from {} rejected: spread {:.3}% > {:.3}%",
+                asset,
+                q.venue,
+                q.spread_pct,
+                config.cex_max_spread_pct
+            );
+            false
+        })
         .collect::<Vec<_>>();
 
-    if quotes.len() < min_sources {
+    if quotes.len() < config.cex_lag_min_sources {
         return None;
     }
 
-    let mut mids = quotes.iter().map(|q| q.mid).collect::<Vec<_>>();
-    mids.sort_by(|a, b| a.total_cmp(b));
-    let median = if mids.len() % 2 == 0 {
-        let right = mids.len() / 2;
-        let left = right - 1;
-        (mids[left] + mids[right]) / 2.0
+    let low = quotes.iter().map(|q| q.mid).fold(f64::INFINITY, f64::min);
+    let high = quotes
+        .iter()
+        .map(|q| q.mid)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let disagreement_pct = (high - low) / low * 100.0;
+    if disagreement_pct > config.cex_agreement_pct {
+        log_err!(
+            "CEX {} venues disagree: {:.2}..{:.2} ({:.3}% > {:.3}%); no reference",
+            asset,
+            low,
+            high,
+            disagreement_pct,
+            config.cex_agreement_pct
+        );
+        return None;
+    }
+
+    let venue_weights = &config.cex_venue_weights;
+    let reference_price = if venue_weights.is_empty() {
+        median(quotes.iter().map(|q| q.mid).collect())
     } else {
-        mids[mids.len() / 2]
+        let weighted = quotes
+            .iter()
+            .map(|q| (q.mid, venue_weights.get(&q.venue).copied().unwrap_or(1.0)))
+            .collect::<Vec<_>>();
+        weighted_median(&weighted)
     };
 
+    let now = Utc::now();
+    let oldest = quotes
+        .iter()
+        .map(|q| (now - q.observed_at).to_std().unwrap_or_default())
+        .max()
+        .unwrap_or_default();
+    let confidence = confidence_score(
+        quotes.len(),
+        venues,
+        disagreement_pct,
+        config.cex_agreement_pct,
+        oldest,
+    );
+
     Some(AssetReference {
         asset: asset.to_string(),
-        reference_price: median,
+        reference_price,
         quotes,
+        confidence,
     })
 }
 
+fn confidence_score(
+    sources: usize,
+    venues: usize,
+    disagreement_pct: f64,
+    agreement_pct: f64,
+    oldest: Duration,
+) -> f64 {
+    if sources == 0 || venues == 0 {
+        return 0.0;
+    }
+    let coverage = (sources as f64 / venues as f64).min(1.0);
+    let agreement = if agreement_pct > 0.0 {
+        (1.0 - disagreement_pct / agreement_pct).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let freshness = (1.0 - oldest.as_secs_f64() / QUOTE_STALE_AFTER.as_secs_f64()).clamp(0.0, 1.0);
+    coverage * agreement * freshness
+}
+
+fn spread_pct(bid: f64, ask: f64) -> f64 {
+    let mid = (bid + ask) / 2.0;
+    (ask - bid) / mid * 100.0
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    if values.len().is_multiple_of(2) {
+        let right = values.len() / 2;
+        let left = right - 1;
+        (values[left] + values[right]) / 2.0
+    } else {
+        values[values.len() / 2]
+    }
+}
+
+fn weighted_median(quotes: &[(f64, f64)]) -> f64 {
+    let mut sorted = quotes.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total = sorted.iter().map(|(_, w)| w).sum::<f64>();
+    if total <= 0.0 {
+        return median(sorted.iter().map(|(v, _)| *v).collect());
+    }
+
+    let half = total / 2.0;
+    let mut cumulative = 0.0;
+    for (idx, (value, weight)) in sorted.iter().enumerate() {
+        cumulative += weight;
+        if cumulative > half {
+            return *value;
+        }
+        if cumulative == half {
+            if let Some((next, _)) = sorted.get(idx + 1) {
+                return (value + next) / 2.0;
+            }
+            return *value;
+        }
+    }
+    sorted.last().map(|(v, _)| *v).unwrap_or(0.0)
+}
+
 fn fetch_coinbase_mid(http: &HttpClient, product: &str) -> Result<VenueQuote> {
     #[derive(Deserialize)]
     struct CoinbaseTicker {
         bid: String,
         ask: String,
+        #[serde(default)]
+        time: Option<String>,
     }
 
     let url = format!(
@@ -99,6 +352,7 @@ fn fetch_coinbase_mid(http: &HttpClient, product: &str) -> Result<VenueQuote> {
     );
     let payload: CoinbaseTicker = http
         .get(url)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .context("coinbase request failed")?
         .error_for_status()
@@ -112,9 +366,17 @@ fn fetch_coinbase_mid(http: &HttpClient, product: &str) -> Result<VenueQuote> {
         return Err(anyhow!("coinbase invalid bid/ask"));
     }
 
+    let observed_at = payload
+        .time
+        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
     Ok(VenueQuote {
         venue: "coinbase".to_string(),
         mid: (bid + ask) / 2.0,
+        spread_pct: spread_pct(bid, ask),
+        observed_at,
     })
 }
 
@@ -122,6 +384,7 @@ fn fetch_kraken_mid(http: &HttpClient, pair: &str) -> Result<VenueQuote> {
     let url = format!("https://api.kraken.com/0/public/Ticker?pair={}", pair);
     let payload: Value = http
         .get(url)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .context("kraken request failed")?
         .error_for_status()
@@ -163,6 +426,8 @@ fn fetch_kraken_mid(http: &HttpClient, pair: &str) -> Result<VenueQuote> {
     Ok(VenueQuote {
         venue: "kraken".to_string(),
         mid: (bid + ask) / 2.0,
+        spread_pct: spread_pct(bid, ask),
+        observed_at: Utc::now(),
     })
 }
 
@@ -181,6 +446,7 @@ fn fetch_binance_mid(http: &HttpClient, symbol: &str) -> Result<VenueQuote> {
     );
     let payload: BinanceBookTicker = http
         .get(url)
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .context("binance request failed")?
         .error_for_status()
@@ -203,5 +469,34 @@ fn fetch_binance_mid(http: &HttpClient, symbol: &str) -> Result<VenueQuote> {
     Ok(VenueQuote {
         venue: "binance".to_string(),
         mid: (bid + ask) / 2.0,
+        spread_pct: spread_pct(bid, ask),
+        observed_at: Utc::now(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(sources: usize, disagreement_pct: f64, oldest_secs: u64) -> f64 {
+        confidence_score(
+            sources,
+            3,
+            disagreement_pct,
+            0.5,
+            Duration::from_secs(oldest_secs),
+        )
+    }
+
+    #[test]
+    fn confidence_score_combines_coverage_agreement_and_staleness() {
+        assert!((score(3, 0.0, 0) - 1.0).abs() < 1e-9);
+        assert!((score(2, 0.0, 0) - 2.0 / 3.0).abs() < 1e-9);
+        assert!((score(3, 0.25, 0) - 0.5).abs() < 1e-9);
+        assert!((score(3, 0.0, 5) - 0.5).abs() < 1e-9);
+        assert!((score(2, 0.25, 5) - 2.0 / 3.0 * 0.5 * 0.5).abs() < 1e-9);
+        assert_eq!(score(3, 0.5, 0), 0.0);
+        assert_eq!(score(3, 0.0, 10), 0.0);
+        assert_eq!(score(0, 0.0, 0), 0.0);
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::config::Config;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "kalshi-15m-bot",
+    version,
+    about = "Kalshi 15m crypto arbitrage bot"
+)]
+pub struct Cli {
+    #[arg(
+        long,
+        conflicts_with = "watch",
+        help = "Run a single pass and exit (default)"
+    )]
+    pub once: bool,
+    #[arg(
+        long,
+        help = "Run continuously, sleeping --interval seconds between passes"
+    )]
+    pub watch: bool,
+    #[arg(
+        long,
+        default_value_t = 20,
+        requires = "watch",
+        help = "Seconds between passes in --watch mode"
+    )]
+    pub interval: u64,
+    #[arg(long, conflicts_with = "live", help = "Force DRY_RUN=true")]
+    pub dry_run: bool,
+    #[arg(long, help = "Force DRY_RUN=false")]
+    pub live: bool,
+    #[arg(long, help = "Print the resolved config (secrets redacted) and exit")]
+    pub print_config: bool,
+    #[arg(long, help = "Check credentials and exchange connectivity, then exit")]
+    pub validate: bool,
+    #[arg(
+        long,
+        value_name = "ENV_FILE",
+        help = "Evaluate the same markets under the current config and one with ENV_FILE overrides, print the differences, then exit"
+    )]
+    pub compare: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn apply(&self, config: &mut Config) {
+        if self.dry_run {
+            config.dry_run = true;
+        }
+        if self.live {
+            config.dry_run = false;
+        }
+        if self.validate {
+            config.validate_only = true;
+        }
+        config.watch = self.watch;
+    }
+}
@@ -1,151 +1,912 @@
+use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::log_err;
+
+/// How `pick_opportunities` weighs the CEX lag signal against the combined
+/// gate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TradeMode {
+    /// Requires a lag signal >= threshold (when the CEX scan is enabled) and a
+    /// passing combined gate; a signal alone never overrides a failing gate.
+    Passive,
+    /// Trades any market passing the combined gate, with or without a signal.
+    Aggressive,
+    /// Never trades without a lag signal >= threshold; with the CEX scan
+    /// disabled nothing qualifies. When the combined gate fails but the signal
+    /// is present, buys only the side the signal favors (YES on positive lag,
+    /// NO on negative).
+    SignalOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeModel {
+    None,
+    Taker,
+    Maker,
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub base_url: String,
     pub api_prefix: String,
     pub api_key: String,
+    pub user_agent: String,
+    pub http_timeout_secs: u64,
+    pub http_pool_max_idle: usize,
+    pub http_pool_idle_secs: u64,
     pub private_key_path: Option<PathBuf>,
     pub private_key_pem: Option<String>,
     pub dry_run: bool,
+    pub validate_only: bool,
+    pub watch: bool,
+    pub dry_run_use_sandbox: bool,
+    pub sandbox_base_url: String,
+    pub mock_generate: bool,
     pub btc_only: bool,
     pub crypto_only: bool,
+    pub disable_asset_filter: bool,
     pub crypto_assets: Vec<String>,
+    pub crypto_synonyms: HashMap<String, Vec<String>>,
+    pub live_assets: Vec<String>,
+    pub allowed_statuses: Vec<String>,
+    pub explicit_tickers: Vec<String>,
+    pub explain_ticker: Option<String>,
     pub event_ticker_prefixes: Vec<String>,
     pub event_series_tickers: Vec<String>,
     pub min_close_ts: Option<i64>,
+    pub max_close_ts: Option<i64>,
+    pub close_within_secs: i64,
     pub interval_regex: String,
+    pub interval_match_fields: Vec<String>,
     pub combined_max_price: f64,
+    pub combined_ttl_tightening: f64,
+    pub combined_ttl_horizon_secs: i64,
+    pub combined_min_price: f64,
+    pub combined_metric: String,
+    pub max_gap: f64,
+    pub min_seconds_to_close: i64,
     pub order_count: i64,
+    pub order_count_by_asset: HashMap<String, i64>,
+    pub min_quantity: i64,
+    pub price_improve_cents: i64,
+    pub price_decimals: usize,
+    pub max_decisions: usize,
+    pub shuffle_markets: bool,
+    pub shuffle_seed: Option<u64>,
+    pub max_orders_per_run: usize,
+    pub max_orders_per_market: usize,
+    pub max_consecutive_order_errors: usize,
+    pub killed_alert_threshold: usize,
+    pub unwind_on_leg_failure: bool,
     pub check_exchange: bool,
+    pub resume_wait_secs: i64,
+    pub reconcile: bool,
+    pub net_positions: bool,
+    pub use_server_time: bool,
+    pub fixed_now_rfc3339: Option<DateTime<Utc>>,
     pub time_in_force: String,
+    pub resting_mode: bool,
+    pub cancel_before_close: i64,
+    pub max_amend_cents: i64,
+    pub fast_use_market: bool,
+    pub respect_model_in_fast_band: bool,
+    pub fast_band: (f64, f64),
+    pub fast_band_by_asset: HashMap<String, (f64, f64)>,
+    pub final_secs: i64,
+    pub submit_offset_secs: i64,
+    pub final_combined_max: f64,
+    pub single_leg_mode: bool,
     pub discover_btc_events: bool,
     pub discover_series: bool,
+    pub fallback_to_all_markets: Option<bool>,
     pub series_category: String,
     pub series_frequency: String,
+    pub series_cache_path: Option<PathBuf>,
+    pub series_fetch_concurrency: usize,
+    pub series_fetch_jitter_ms: u64,
+    pub series_cache_ttl_secs: i64,
     pub events_limit: i64,
+    pub max_markets: usize,
+    pub max_pages: usize,
     pub log_decisions: bool,
+    pub decisions_json_path: Option<PathBuf>,
+    pub record_dir: Option<PathBuf>,
+    pub replay_dir: Option<PathBuf>,
+    pub order_webhook_url: Option<String>,
+    pub order_tag: Option<String>,
+    pub opportunity_webhook_url: Option<String>,
+    pub slack_quiet_on_idle: bool,
+    pub slack_error_lines: usize,
+    pub slack_error_context_lines: usize,
+    pub watchdog_secs: u64,
+    pub watchdog_abort: bool,
+    pub run_deadline_secs: u64,
     pub enable_cex_lag_scan: bool,
     pub cex_lag_threshold: f64,
-    pub cex_lag_require_signal: bool,
+    pub trade_mode: TradeMode,
+    pub use_mid_for_signal: bool,
+    pub cex_lag_max_sane: f64,
+    pub strike_sanity_pct: f64,
+    pub min_strike_distance_bps: f64,
     pub cex_lag_min_sources: usize,
+    pub cex_venue_weights: HashMap<String, f64>,
+    pub cex_symbols: HashMap<String, HashMap<String, String>>,
+    pub cex_venue_failure_limit: usize,
+    pub cex_venue_cooldown_secs: u64,
+    pub cex_max_spread_pct: f64,
+    pub cex_agreement_pct: f64,
+    pub cex_min_confidence: f64,
+    pub cex_refresh_ms: u64,
+    pub reference_bias_bps: HashMap<String, f64>,
+    pub model_combined_edge: f64,
+    pub fee_model: FeeModel,
+    pub index_path: String,
+    pub index_delta_alert_pct: f64,
+    pub equal_market_prob: f64,
+    pub prob_clamp: f64,
 }
 
 impl Config {
     pub fn from_env() -> Self {
-        let raw_base_url = env::var("KALSHI_BASE_URL")
+        Self::from_env_with(&HashMap::new())
+    }
+
+    /// Like `from_env`, but `overrides` take precedence over the process
+    /// environment (which is left untouched).
+    pub fn from_env_with(overrides: &HashMap<String, String>) -> Self {
+        Self::from_lookup(|key| match overrides.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => env::var(key),
+        })
+    }
+
+    /// Built-in defaults only, ignoring the process environment, so tests do
+    /// not depend on the developer's shell.
+    #[cfg(test)]
+    pub fn defaults() -> Self {
+        Self::from_lookup(|_| Err(env::VarError::NotPresent))
+    }
+
+    fn from_lookup<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Result<String, env::VarError>,
+    {
+        let raw_base_url = var("KALSHI_BASE_URL")
             .unwrap_or_else(|_| "https://api.elections.kalshi.com/trade-api/v2".to_string());
         let (base_url, api_prefix) = split_base_url(&raw_base_url);
-        let api_key = env::var("KALSHI_API_KEY").unwrap_or_default();
-        let private_key_path = env::var("KALSHI_PRIVATE_KEY_PATH").ok().map(PathBuf::from);
-        let private_key_pem = env::var("KALSHI_PRIVATE_KEY_PEM")
+        let file_credentials = var("CREDENTIALS_FILE")
+            .ok()
+            .and_then(|path| load_credentials_file(Path::new(&path)))
+            .unwrap_or_default();
+        let api_key = var("KALSHI_API_KEY")
             .ok()
-            .or_else(|| env::var("KALSHI_API_SECRET").ok());
-        let dry_run = env::var("DRY_RUN")
+            .or(file_credentials.api_key)
+            .unwrap_or_default();
+        let user_agent = var("USER_AGENT")
+            .unwrap_or_else(|_| format!("kalshi-bot/{}", env!("CARGO_PKG_VERSION")));
+        let http_timeout_secs = var("HTTP_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30u64);
+        let http_pool_max_idle = var("HTTP_POOL_MAX_IDLE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8usize);
+        let http_pool_idle_secs = var("HTTP_POOL_IDLE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(90u64);
+        let private_key_path = var("KALSHI_PRIVATE_KEY_PATH").ok().map(PathBuf::from);
+        let private_key_pem = var("KALSHI_PRIVATE_KEY_PEM")
+            .ok()
+            .or_else(|| var("KALSHI_API_SECRET").ok())
+            .or(file_credentials.private_key_pem);
+        let dry_run = var("DRY_RUN")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(true);
-        let btc_only = env::var("BTC_ONLY")
+        let validate_only = var("VALIDATE_ONLY")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
-        let crypto_only = env::var("CRYPTO_ONLY")
-            .map(|v| v != "false")
-            .unwrap_or(true);
-        let crypto_assets = env::var("CRYPTO_ASSETS")
+        let dry_run_use_sandbox = var("DRY_RUN_USE_SANDBOX")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let sandbox_base_url = var("SANDBOX_BASE_URL")
+            .unwrap_or_else(|_| "https://demo-api.kalshi.co/trade-api/v2".to_string());
+        let mock_generate = var("MOCK_GENERATE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let btc_only = var("BTC_ONLY")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let crypto_only = var("CRYPTO_ONLY").map(|v| v != "false").unwrap_or(true);
+        let disable_asset_filter = var("DISABLE_ASSET_FILTER")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let crypto_assets = var("CRYPTO_ASSETS")
             .unwrap_or_else(|_| "BTC,ETH,SOL".to_string())
             .split(',')
             .map(|s| s.trim().to_lowercase())
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>();
-        let event_ticker_prefixes = env::var("EVENT_TICKER_PREFIXES")
+        let mut crypto_synonyms = HashMap::from([
+            ("btc".to_string(), vec!["bitcoin".to_string()]),
+            ("eth".to_string(), vec!["ethereum".to_string()]),
+            ("sol".to_string(), vec!["solana".to_string()]),
+        ]);
+        for entry in var("CRYPTO_SYNONYMS").unwrap_or_default().split(',') {
+            let Some((asset, words)) = entry.split_once(':') else {
+                continue;
+            };
+            let asset = asset.trim().to_lowercase();
+            if asset.is_empty() {
+                continue;
+            }
+            let list = crypto_synonyms.entry(asset).or_default();
+            for word in words.split('|') {
+                let word = word.trim().to_lowercase();
+                if !word.is_empty() && !list.contains(&word) {
+                    list.push(word);
+                }
+            }
+        }
+        let live_assets = var("LIVE_ASSETS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let allowed_statuses = var("ALLOWED_STATUSES")
+            .unwrap_or_else(|_| "open,active".to_string())
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let explain_ticker = var("EXPLAIN_TICKER")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let explicit_tickers = var("EXPLICIT_TICKERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let event_ticker_prefixes = var("EVENT_TICKER_PREFIXES")
             .unwrap_or_else(|_| "KXBTC15M,KXETH15M,KXSOL15M".to_string())
             .split(',')
             .map(|s| s.trim().to_uppercase())
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>();
-        let event_series_tickers = env::var("EVENT_SERIES_TICKERS")
+        let event_series_tickers = var("EVENT_SERIES_TICKERS")
             .unwrap_or_else(|_| "KXBTC15M,KXETH15M,KXSOL15M".to_string())
             .split(',')
             .map(|s| s.trim().to_uppercase())
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>();
-        let min_close_ts = env::var("MIN_CLOSE_TS").ok().and_then(|v| v.parse().ok());
-        let interval_regex = env::var("INTERVAL_REGEX")
-            .unwrap_or_else(|_| "(?i)\\b15\\s?m(in(ute)?s?)?\\b".to_string());
-        let combined_max_price = env::var("COMBINED_MAX_PRICE")
+        let min_close_ts = var("MIN_CLOSE_TS").ok().and_then(|v| v.parse().ok());
+        let max_close_ts = var("MAX_CLOSE_TS").ok().and_then(|v| v.parse().ok());
+        let close_within_secs = var("CLOSE_WITHIN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let interval_match_fields = var("INTERVAL_MATCH_FIELDS")
+            .unwrap_or_else(|_| "title,subtitle,event_ticker".to_string())
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let interval_regex =
+            var("INTERVAL_REGEX").unwrap_or_else(|_| "(?i)\\b15\\s?m(in(ute)?s?)?\\b".to_string());
+        let combined_max_price = var("COMBINED_MAX_PRICE")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(1.0);
-        let order_count = env::var("ORDER_COUNT")
+        let combined_ttl_tightening = var("COMBINED_TTL_TIGHTENING")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let combined_ttl_horizon_secs = var("COMBINED_TTL_HORIZON_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900);
+        let combined_min_price = var("COMBINED_MIN_PRICE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let combined_metric = var("COMBINED_METRIC")
+            .map(|v| v.trim().to_lowercase())
+            .unwrap_or_else(|_| "sum".to_string());
+        let max_gap = var("MAX_GAP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let min_seconds_to_close = var("MIN_SECONDS_TO_CLOSE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        let order_count = var("ORDER_COUNT")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(1);
-        let check_exchange = env::var("CHECK_EXCHANGE")
+        let order_count_by_asset = var("ORDER_COUNT_BY_ASSET")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (asset, count) = entry.split_once(':')?;
+                let count = count.trim().parse::<i64>().ok()?;
+                Some((asset.trim().to_uppercase(), count))
+            })
+            .filter(|(asset, count)| !asset.is_empty() && *count > 0)
+            .collect::<HashMap<_, _>>();
+        let price_improve_cents = var("PRICE_IMPROVE_CENTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let price_decimals = var("PRICE_DECIMALS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2usize);
+        let min_quantity = var("MIN_QUANTITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+        let shuffle_markets = var("SHUFFLE_MARKETS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let shuffle_seed = var("SHUFFLE_SEED").ok().and_then(|v| v.parse().ok());
+        let max_decisions = var("MAX_DECISIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0usize);
+        let max_orders_per_run = var("MAX_ORDERS_PER_RUN")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20usize);
+        let max_orders_per_market = var("MAX_ORDERS_PER_MARKET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2usize);
+        let max_consecutive_order_errors = var("MAX_CONSECUTIVE_ORDER_ERRORS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3usize);
+        let killed_alert_threshold = var("KILLED_ALERT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3usize);
+        let unwind_on_leg_failure = var("UNWIND_ON_LEG_FAILURE")
             .map(|v| v != "false")
             .unwrap_or(true);
-        let time_in_force =
-            env::var("TIME_IN_FORCE").unwrap_or_else(|_| "fill_or_kill".to_string());
-        let discover_btc_events = env::var("DISCOVER_BTC_EVENTS")
+        let resume_wait_secs = var("RESUME_WAIT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let check_exchange = var("CHECK_EXCHANGE").map(|v| v != "false").unwrap_or(true);
+        let reconcile = var("RECONCILE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let net_positions = var("NET_POSITIONS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let use_server_time = var("USE_SERVER_TIME")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let fixed_now_rfc3339 = var("FIXED_NOW_RFC3339")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .and_then(|v| match DateTime::parse_from_rfc3339(v.trim()) {
+                Ok(ts) => Some(ts.with_timezone(&Utc)),
+                Err(err) => {
+                    log_err!("Ignoring invalid FIXED_NOW_RFC3339 {:?}: {}", v, err);
+                    None
+                }
+            });
+        let resting_mode = var("RESTING_MODE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let time_in_force = if resting_mode {
+            "good_till_canceled".to_string()
+        } else {
+            var("TIME_IN_FORCE").unwrap_or_else(|_| "fill_or_kill".to_string())
+        };
+        let max_amend_cents = var("MAX_AMEND_CENTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let cancel_before_close = var("CANCEL_BEFORE_CLOSE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let fast_use_market = var("FAST_USE_MARKET")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let fast_band = var("FAST_BAND")
+            .ok()
+            .and_then(|v| parse_band("FAST_BAND", &v))
+            .unwrap_or((0.90, 0.97));
+        let fast_band_by_asset = var("FAST_BAND_BY_ASSET")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (asset, band) = entry.split_once(':')?;
+                let band = parse_band("FAST_BAND_BY_ASSET", band)?;
+                Some((asset.trim().to_uppercase(), band))
+            })
+            .filter(|(asset, _)| !asset.is_empty())
+            .collect::<HashMap<_, _>>();
+        let respect_model_in_fast_band = var("RESPECT_MODEL_IN_FAST_BAND")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let submit_offset_secs = var("SUBMIT_OFFSET_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let final_secs = var("FINAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let final_combined_max = var("FINAL_COMBINED_MAX")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.99);
+        let single_leg_mode = var("SINGLE_LEG_MODE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let discover_btc_events = var("DISCOVER_BTC_EVENTS")
             .map(|v| v != "false")
             .unwrap_or(true);
-        let discover_series = env::var("DISCOVER_SERIES")
+        let discover_series = var("DISCOVER_SERIES")
             .map(|v| v != "false")
             .unwrap_or(false);
-        let series_category = env::var("SERIES_CATEGORY").unwrap_or_else(|_| "crypto".to_string());
+        let fallback_to_all_markets = var("FALLBACK_TO_ALL_MARKETS").ok().map(|v| v != "false");
+        let series_category = var("SERIES_CATEGORY").unwrap_or_else(|_| "crypto".to_string());
         let series_frequency =
-            env::var("SERIES_FREQUENCY").unwrap_or_else(|_| "fifteen_min".to_string());
-        let events_limit = env::var("EVENTS_LIMIT")
+            var("SERIES_FREQUENCY").unwrap_or_else(|_| "fifteen_min".to_string());
+        let series_fetch_concurrency = var("SERIES_FETCH_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4usize);
+        let series_fetch_jitter_ms = var("SERIES_FETCH_JITTER_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100u64);
+        let series_cache_path = var("SERIES_CACHE_PATH")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from);
+        let series_cache_ttl_secs = var("SERIES_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        let events_limit = var("EVENTS_LIMIT")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(200);
-        let log_decisions = env::var("LOG_DECISIONS")
+        let max_markets = var("MAX_MARKETS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000usize);
+        let max_pages = var("MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100usize);
+        let log_decisions = var("LOG_DECISIONS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let decisions_json_path = var("DECISIONS_JSON_PATH")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from);
+        let record_dir = var("RECORD_DIR")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from);
+        let replay_dir = var("REPLAY_DIR")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from);
+        let order_tag = var("ORDER_TAG")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let order_webhook_url = var("ORDER_WEBHOOK_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let opportunity_webhook_url = var("OPPORTUNITY_WEBHOOK_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let slack_error_lines = var("SLACK_ERROR_LINES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(6usize);
+        let slack_error_context_lines = var("SLACK_ERROR_CONTEXT_LINES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3usize);
+        let slack_quiet_on_idle = var("SLACK_QUIET_ON_IDLE")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
-        let enable_cex_lag_scan = env::var("ENABLE_CEX_LAG_SCAN")
+        let watchdog_secs = var("WATCHDOG_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0u64);
+        let run_deadline_secs = var("RUN_DEADLINE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let watchdog_abort = var("WATCHDOG_ABORT")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let enable_cex_lag_scan = var("ENABLE_CEX_LAG_SCAN")
             .map(|v| v != "false")
             .unwrap_or(true);
-        let cex_lag_threshold = env::var("CEX_LAG_THRESHOLD")
+        let cex_lag_threshold = var("CEX_LAG_THRESHOLD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(0.08);
-        let cex_lag_require_signal = env::var("CEX_LAG_REQUIRE_SIGNAL")
+        let cex_lag_require_signal = var("CEX_LAG_REQUIRE_SIGNAL")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
-        let cex_lag_min_sources = env::var("CEX_LAG_MIN_SOURCES")
+        let fee_model = match var("FEE_MODEL")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "taker" => FeeModel::Taker,
+            "maker" => FeeModel::Maker,
+            _ => FeeModel::None,
+        };
+        let trade_mode = match var("TRADE_MODE")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .replace('_', "-")
+            .as_str()
+        {
+            "passive" => TradeMode::Passive,
+            "aggressive" => TradeMode::Aggressive,
+            "signal-only" => TradeMode::SignalOnly,
+            _ if cex_lag_require_signal => TradeMode::Passive,
+            _ => TradeMode::Aggressive,
+        };
+        if trade_mode == TradeMode::SignalOnly && !enable_cex_lag_scan {
+            log_err!("WARN: TRADE_MODE=signal-only with ENABLE_CEX_LAG_SCAN=false; no market will qualify.");
+        }
+        let cex_lag_max_sane = var("CEX_LAG_MAX_SANE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.5);
+        let strike_sanity_pct = var("STRIKE_SANITY_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50.0);
+        let min_strike_distance_bps = var("MIN_STRIKE_DISTANCE_BPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let use_mid_for_signal = var("USE_MID_FOR_SIGNAL")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        let cex_lag_min_sources = var("CEX_LAG_MIN_SOURCES")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(2usize);
+        let cex_venue_weights = var("CEX_VENUE_WEIGHTS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (venue, weight) = entry.split_once(':')?;
+                let weight = weight.trim().parse::<f64>().ok()?;
+                Some((venue.trim().to_lowercase(), weight))
+            })
+            .filter(|(venue, weight)| !venue.is_empty() && *weight > 0.0)
+            .collect::<HashMap<_, _>>();
+        let mut cex_symbols: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for entry in var("CEX_SYMBOLS").unwrap_or_default().split(',') {
+            if entry.trim().is_empty() {
+                continue;
+            }
+            let parts = entry.split(':').map(str::trim).collect::<Vec<_>>();
+            let [asset, venue, symbol] = parts[..] else {
+                log_err!(
+                    "Ignoring CEX_SYMBOLS entry {:?} (expected asset:venue:symbol)",
+                    entry
+                );
+                continue;
+            };
+            let venue = venue.to_lowercase();
+            if !["coinbase", "kraken", "binance"].contains(&venue.as_str()) || symbol.is_empty() {
+                log_err!(
+                    "Ignoring CEX_SYMBOLS entry {:?} (unknown venue or empty symbol)",
+                    entry
+                );
+                continue;
+            }
+            cex_symbols
+                .entry(asset.to_uppercase())
+                .or_default()
+                .insert(venue, symbol.to_string());
+        }
+        let cex_venue_failure_limit = var("CEX_VENUE_FAILURE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3usize);
+        let cex_venue_cooldown_secs = var("CEX_VENUE_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300u64);
+        let cex_max_spread_pct = var("CEX_MAX_SPREAD_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.2);
+        let cex_refresh_ms = var("CEX_REFRESH_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let cex_min_confidence = var("CEX_MIN_CONFIDENCE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let cex_agreement_pct = var("CEX_AGREEMENT_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.3);
+        let reference_bias_bps = var("REFERENCE_BIAS_BPS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (asset, bps) = entry.split_once(':')?;
+                let bps = bps.trim().parse::<f64>().ok()?;
+                Some((asset.trim().to_uppercase(), bps))
+            })
+            .filter(|(asset, bps)| !asset.is_empty() && bps.is_finite())
+            .collect::<HashMap<_, _>>();
+        let index_path = var("KALSHI_INDEX_PATH").unwrap_or_default();
+        let index_delta_alert_pct = var("INDEX_DELTA_ALERT_PCT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.1);
+        let model_combined_edge = var("MODEL_COMBINED_EDGE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.02);
+        let prob_clamp = var("PROB_CLAMP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1e-4);
+        let equal_market_prob = var("EQUAL_MARKET_PROB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.02);
 
         Self {
             base_url,
             api_prefix,
             api_key,
+            user_agent,
+            http_timeout_secs,
+            http_pool_max_idle,
+            http_pool_idle_secs,
             private_key_path,
             private_key_pem,
             dry_run,
+            validate_only,
+            watch: false,
+            dry_run_use_sandbox,
+            sandbox_base_url,
+            mock_generate,
             btc_only,
             crypto_only,
+            disable_asset_filter,
             crypto_assets,
+            crypto_synonyms,
+            live_assets,
+            allowed_statuses,
+            explicit_tickers,
+            explain_ticker,
             event_ticker_prefixes,
             event_series_tickers,
             min_close_ts,
+            max_close_ts,
+            close_within_secs,
             interval_regex,
+            interval_match_fields,
             combined_max_price,
+            combined_ttl_tightening,
+            combined_ttl_horizon_secs,
+            combined_min_price,
+            combined_metric,
+            max_gap,
+            min_seconds_to_close,
             order_count,
+            order_count_by_asset,
+            min_quantity,
+            price_improve_cents,
+            price_decimals,
+            max_decisions,
+            shuffle_markets,
+            shuffle_seed,
+            max_orders_per_run,
+            max_orders_per_market,
+            max_consecutive_order_errors,
+            killed_alert_threshold,
+            unwind_on_leg_failure,
             check_exchange,
+            resume_wait_secs,
+            reconcile,
+            net_positions,
+            use_server_time,
+            fixed_now_rfc3339,
             time_in_force,
+            resting_mode,
+            cancel_before_close,
+            max_amend_cents,
+            fast_use_market,
+            respect_model_in_fast_band,
+            fast_band,
+            fast_band_by_asset,
+            final_secs,
+            submit_offset_secs,
+            final_combined_max,
+            single_leg_mode,
             discover_btc_events,
             discover_series,
+            fallback_to_all_markets,
             series_category,
             series_frequency,
+            series_fetch_concurrency,
+            series_fetch_jitter_ms,
+            series_cache_path,
+            series_cache_ttl_secs,
             events_limit,
+            max_markets,
+            max_pages,
             log_decisions,
+            decisions_json_path,
+            record_dir,
+            replay_dir,
+            order_webhook_url,
+            order_tag,
+            opportunity_webhook_url,
+            slack_quiet_on_idle,
+            slack_error_lines,
+            slack_error_context_lines,
+            watchdog_secs,
+            watchdog_abort,
+            run_deadline_secs,
             enable_cex_lag_scan,
             cex_lag_threshold,
-            cex_lag_require_signal,
+            trade_mode,
+            use_mid_for_signal,
+            cex_lag_max_sane,
+            strike_sanity_pct,
+            min_strike_distance_bps,
             cex_lag_min_sources,
+            cex_venue_weights,
+            cex_symbols,
+            cex_venue_failure_limit,
+            cex_venue_cooldown_secs,
+            cex_max_spread_pct,
+            cex_agreement_pct,
+            cex_min_confidence,
+            cex_refresh_ms,
+            reference_bias_bps,
+            model_combined_edge,
+            fee_model,
+            index_path,
+            index_delta_alert_pct,
+            equal_market_prob,
+            prob_clamp,
+        }
+    }
+
+    pub fn redacted(&self) -> Self {
+        let redact = |value: &mut Option<String>| {
+            if value.is_some() {
+                *value = Some("<redacted>".to_string());
+            }
+        };
+        let mut config = self.clone();
+        redact(&mut config.private_key_pem);
+        redact(&mut config.order_webhook_url);
+        redact(&mut config.opportunity_webhook_url);
+        if !config.api_key.is_empty() {
+            config.api_key = "<redacted>".to_string();
         }
+        config
+    }
+
+    pub fn run_deadline(&self) -> Option<Duration> {
+        (self.run_deadline_secs > 0).then(|| Duration::from_secs(self.run_deadline_secs))
+    }
+
+    pub fn fast_band_for(&self, asset: Option<&str>) -> (f64, f64) {
+        asset
+            .and_then(|asset| self.fast_band_by_asset.get(asset))
+            .copied()
+            .unwrap_or(self.fast_band)
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        !self.api_key.is_empty()
+            && (self.private_key_pem.is_some() || self.private_key_path.is_some())
+    }
+
+    pub fn fixed_now(&self) -> Option<DateTime<Utc>> {
+        self.fixed_now_rfc3339.filter(|_| self.dry_run)
+    }
+
+    pub fn order_count_for(&self, asset: Option<&str>) -> i64 {
+        asset
+            .and_then(|a| self.order_count_by_asset.get(a).copied())
+            .unwrap_or(self.order_count)
+    }
+
+    pub fn is_live_asset(&self, asset: Option<&str>) -> bool {
+        if self.live_assets.is_empty() {
+            return true;
+        }
+        asset
+            .map(|a| self.live_assets.iter().any(|live| live == a))
+            .unwrap_or(false)
+    }
+
+    pub fn sandboxed(&self) -> Self {
+        let (base_url, api_prefix) = split_base_url(&self.sandbox_base_url);
+        let mut config = self.clone();
+        config.base_url = base_url;
+        config.api_prefix = api_prefix;
+        config.dry_run = false;
+        config
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FileCredentials {
+    #[serde(default)]
+    api_key: Option<String>,
+    #[serde(default)]
+    private_key_pem: Option<String>,
+}
+
+fn load_credentials_file(path: &Path) -> Option<FileCredentials> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            log_err!("Credentials file {:?} unreadable: {}", path, err);
+            return None;
+        }
+    };
+    match serde_json::from_str(&raw) {
+        Ok(creds) => Some(creds),
+        Err(err) => {
+            log_err!("Credentials file {:?} invalid JSON: {}", path, err);
+            None
+        }
+    }
+}
+
+fn parse_band(name: &str, raw: &str) -> Option<(f64, f64)> {
+    let band = raw
+        .split_once('-')
+        .and_then(|(low, high)| Some((low.trim().parse().ok()?, high.trim().parse().ok()?)))
+        .filter(|(low, high): &(f64, f64)| low < high);
+    if band.is_none() {
+        log_err!(
+            "Ignoring invalid {} band {:?} (expected low-high with low < high)",
+            name,
+            raw
+        );
     }
+    band
 }
 
 fn split_base_url(raw: &str) -> (String, String) {
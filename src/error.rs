@@ -0,0 +1,24 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum BotError {
+    ExchangeInactive(String),
+    Auth(String),
+    Network(String),
+    Parse(String),
+    Config(String),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::ExchangeInactive(msg) => write!(f, "exchange inactive: {}", msg),
+            BotError::Auth(msg) => write!(f, "auth error: {}", msg),
+            BotError::Network(msg) => write!(f, "network error: {}", msg),
+            BotError::Parse(msg) => write!(f, "parse error: {}", msg),
+            BotError::Config(msg) => write!(f, "config error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
@@ -0,0 +1,50 @@
+use crate::config::FeeModel;
+
+const TAKER_RATE: f64 = 0.07;
+const MAKER_RATE: f64 = 0.0175;
+
+pub fn trading_fee(price: f64, qty: i64, is_maker: bool) -> f64 {
+    let rate = if is_maker { MAKER_RATE } else { TAKER_RATE };
+    let raw = rate * qty as f64 * price * (1.0 - price);
+    (raw * 100.0 - 1e-9).ceil().max(0.0) / 100.0
+}
+
+pub fn fee_for(model: FeeModel, price: f64, qty: i64) -> f64 {
+    match model {
+        FeeModel::None => 0.0,
+        FeeModel::Taker => trading_fee(price, qty, false),
+        FeeModel::Maker => trading_fee(price, qty, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cents(fee: f64) -> i64 {
+        (fee * 100.0).round() as i64
+    }
+
+    #[test]
+    fn taker_fees_match_published_examples() {
+        assert_eq!(cents(trading_fee(0.50, 100, false)), 175);
+        assert_eq!(cents(trading_fee(0.50, 1, false)), 2);
+        assert_eq!(cents(trading_fee(0.30, 10, false)), 15);
+        assert_eq!(cents(trading_fee(0.01, 1, false)), 1);
+        assert_eq!(cents(trading_fee(0.99, 100, false)), 7);
+    }
+
+    #[test]
+    fn maker_fees_match_published_examples() {
+        assert_eq!(cents(trading_fee(0.50, 100, true)), 44);
+        assert_eq!(cents(trading_fee(0.50, 1, true)), 1);
+        assert_eq!(cents(trading_fee(0.20, 1000, true)), 280);
+    }
+
+    #[test]
+    fn fee_model_selects_rate() {
+        assert_eq!(fee_for(FeeModel::None, 0.50, 100), 0.0);
+        assert_eq!(cents(fee_for(FeeModel::Taker, 0.50, 100)), 175);
+        assert_eq!(cents(fee_for(FeeModel::Maker, 0.50, 100)), 44);
+    }
+}
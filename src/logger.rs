@@ -31,6 +31,14 @@ pub fn log_stderr_fmt(args: Arguments) {
     push_line(&line);
 }
 
+pub fn reset_log() {
+    if let Some(lock) = LOGGER.get() {
+        if let Ok(mut logger) = lock.lock() {
+            logger.lines.clear();
+        }
+    }
+}
+
 pub fn collected_log() -> String {
     if let Some(lock) = LOGGER.get() {
         if let Ok(logger) = lock.lock() {
@@ -1,24 +1,311 @@
 mod cex;
+mod cli;
 mod client;
 mod config;
+mod error;
+mod fees;
 mod logger;
 mod models;
 mod slack;
 mod strategy;
+mod watchdog;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use cex::scan_btc_eth_references;
-use client::{KalshiClient, LiveClient, MockClient};
+use clap::Parser;
+use cli::Cli;
+use client::{build_http_client, KalshiClient, LiveClient, MockClient, ReplayClient};
 use config::Config;
+use error::BotError;
 use logger::collected_log;
 use logger::init_logger;
+use logger::reset_log;
+use models::RestingOrder;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use reqwest::blocking::Client as HttpClient;
+use std::time::Instant;
+use watchdog::Watchdog;
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
     dotenvy::dotenv().ok();
     init_logger();
-    let config = Config::from_env();
+    let mut config = Config::from_env();
+    cli.apply(&mut config);
 
-    let result = run_with_config(&config);
+    if config.submit_offset_secs > 0 && config.submit_offset_secs <= config.min_seconds_to_close {
+        return Err(anyhow!(
+            "SUBMIT_OFFSET_SECS={} must exceed MIN_SECONDS_TO_CLOSE={}; held fast-band orders would always be skipped at submit time",
+            config.submit_offset_secs,
+            config.min_seconds_to_close
+        ));
+    }
+
+    if config.fixed_now_rfc3339.is_some() && !config.dry_run {
+        log_err!("FIXED_NOW_RFC3339 is ignored outside DRY_RUN.");
+    }
+
+    if cli.print_config {
+        println!("{:#?}", config.redacted());
+        return Ok(());
+    }
+
+    let http = build_http_client(&config)?;
+
+    if config.validate_only {
+        return validate(&config, &http);
+    }
+
+    if let Some(path) = &cli.compare {
+        let variant = load_variant(&cli, path)?;
+        return compare(&config, &variant, &http);
+    }
+
+    let watchdog = (config.watchdog_secs > 0).then(|| {
+        Watchdog::spawn(
+            config.watchdog_secs,
+            config.watchdog_abort,
+            std::env::var("SLACK_WEBHOOK_URL").ok(),
+            http.clone(),
+        )
+    });
+
+    if !cli.watch {
+        if let Some(watchdog) = &watchdog {
+            watchdog.reset();
+        }
+        return run_and_report(&config, &http, &mut Vec::new());
+    }
+
+    if config.enable_cex_lag_scan && config.cex_refresh_ms > 0 {
+        log_err!(
+            "Starting background CEX refresher every {}ms.",
+            config.cex_refresh_ms
+        );
+        cex::spawn_cex_refresher(
+            config.clone(),
+            http.clone(),
+            config.cex_refresh_ms,
+            vec!["BTC", "ETH"],
+        );
+    }
+
+    let mut resting = Vec::new();
+    loop {
+        reset_log();
+        if let Some(watchdog) = &watchdog {
+            watchdog.reset();
+        }
+        let _ = run_and_report(&config, &http, &mut resting);
+        if let Some(watchdog) = &watchdog {
+            watchdog.finish();
+        }
+        std::thread::sleep(std::time::Duration::from_secs(cli.interval));
+    }
+}
+
+fn validate(config: &Config, http: &HttpClient) -> Result<()> {
+    if config.api_key.is_empty() {
+        return Err(anyhow!("KALSHI_API_KEY not set"));
+    }
+    let client = LiveClient::new(config.clone(), http.clone())?;
+    match client.exchange_status()? {
+        Some(status) => {
+            log_out!(
+                "Exchange status: exchange_active={}, trading_active={}",
+                status.exchange_active,
+                status.trading_active
+            );
+            if !status.exchange_active || !status.trading_active {
+                return Err(BotError::ExchangeInactive(format!(
+                    "exchange_active={}, trading_active={}",
+                    status.exchange_active, status.trading_active
+                ))
+                .into());
+            }
+        }
+        None => log_out!("Exchange status: unavailable"),
+    }
+    let positions = client
+        .list_positions()
+        .context("authenticated request failed")?;
+    log_out!(
+        "Credentials OK for {}{} ({} positions)",
+        config.base_url,
+        config.api_prefix,
+        positions.len()
+    );
+    let mut unresolved = Vec::new();
+    for ticker in &config.event_series_tickers {
+        match client.get_series(ticker) {
+            Ok(series) => log_out!(
+                "Series {} OK: category={} frequency={}",
+                series.ticker,
+                series.category.as_deref().unwrap_or("unknown"),
+                series.frequency.as_deref().unwrap_or("unknown")
+            ),
+            Err(err) => {
+                log_err!("Series {} did not resolve: {}", ticker, err);
+                unresolved.push(ticker.as_str());
+            }
+        }
+    }
+    if !unresolved.is_empty() {
+        return Err(anyhow!(
+            "unresolved EVENT_SERIES_TICKERS: {}",
+            unresolved.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn load_variant(cli: &Cli, path: &std::path::Path) -> Result<Config> {
+    let overrides = dotenvy::from_path_iter(path)
+        .with_context(|| format!("failed to read overrides from {:?}", path))?
+        .map(|item| item.with_context(|| format!("invalid override in {:?}", path)))
+        .collect::<Result<std::collections::HashMap<_, _>>>()?;
+    let mut variant = Config::from_env_with(&overrides);
+    cli.apply(&mut variant);
+    Ok(variant)
+}
+
+fn compare(base: &Config, variant: &Config, http: &HttpClient) -> Result<()> {
+    if base.has_credentials() {
+        let client = LiveClient::new(base.clone(), http.clone())?;
+        return compare_with(client, base, variant, http);
+    }
+    compare_with(MockClient::new(base.clone()), base, variant, http)
+}
+
+fn compare_with<C: KalshiClient>(
+    client: C,
+    base: &Config,
+    variant: &Config,
+    http: &HttpClient,
+) -> Result<()> {
+    let now = client.now();
+    let markets = client.list_markets()?;
+    let mut cex_assets = markets
+        .iter()
+        .filter_map(|m| m.primary_asset())
+        .filter(|asset| *asset == "BTC" || *asset == "ETH")
+        .collect::<Vec<_>>();
+    cex_assets.sort();
+    cex_assets.dedup();
+    let scan = (base.enable_cex_lag_scan || variant.enable_cex_lag_scan) && !cex_assets.is_empty();
+    let cex_refs = if scan {
+        scan_btc_eth_references(base, http, &cex_assets)
+            .map_err(|err| log_err!("CEX price scan failed: {}", err))
+            .ok()
+    } else {
+        None
+    };
+
+    let positions = std::collections::HashMap::new();
+    let [base_verdicts, variant_verdicts] = [base, variant].map(|config| {
+        strategy::pick_opportunities(config, now, markets.clone(), cex_refs.as_ref(), &positions)
+            .into_iter()
+            .map(|decision| (decision.market.ticker, decision.verdict))
+            .collect::<std::collections::BTreeMap<_, _>>()
+    });
+
+    let mut tickers = base_verdicts
+        .keys()
+        .chain(variant_verdicts.keys())
+        .collect::<Vec<_>>();
+    tickers.sort();
+    tickers.dedup();
+    let rows = tickers
+        .into_iter()
+        .filter(|ticker| base_verdicts.get(*ticker) != variant_verdicts.get(*ticker))
+        .collect::<Vec<_>>();
+
+    let describe = |verdict: Option<&&str>| match verdict {
+        Some(verdict) => format!("QUALIFY {}", verdict),
+        None => "SKIP".to_string(),
+    };
+    log_out!(
+        "COMPARE: {} qualify under base, {} under variant, {} differ",
+        base_verdicts.len(),
+        variant_verdicts.len(),
+        rows.len()
+    );
+    log_out!("{:<32} | {:<36} | {}", "TICKER", "BASE", "VARIANT");
+    for ticker in rows {
+        log_out!(
+            "{:<32} | {:<36} | {}",
+            ticker,
+            describe(base_verdicts.get(ticker)),
+            describe(variant_verdicts.get(ticker))
+        );
+    }
+    Ok(())
+}
+
+struct RunReport {
+    cex_refs: Option<std::collections::HashMap<String, cex::AssetReference>>,
+    markets: usize,
+    requests: usize,
+    halted: Option<String>,
+    killed_alert: Option<String>,
+}
+
+fn format_cex_summary(report: &RunReport) -> Option<String> {
+    let refs = report.cex_refs.as_ref().filter(|refs| !refs.is_empty())?;
+    let mut assets = refs.values().collect::<Vec<_>>();
+    assets.sort_by(|a, b| a.asset.cmp(&b.asset));
+    let summary = assets
+        .iter()
+        .map(|reference| {
+            format!(
+                "{} {} ({}v)",
+                reference.asset,
+                format_usd(reference.reference_price),
+                reference.quotes.len()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(summary)
+}
+
+fn format_footer(started: Instant, report: Option<&RunReport>) -> String {
+    let mut footer = format!("Runtime {:.1}s", started.elapsed().as_secs_f64());
+    if let Some(report) = report {
+        footer.push_str(&format!(
+            " · {} requests · {} markets",
+            report.requests, report.markets
+        ));
+        if let Some(halted) = &report.halted {
+            footer.push_str(&format!(" · halted: {}", halted));
+        }
+    }
+    footer
+}
+
+fn format_usd(value: f64) -> String {
+    let whole = value.round() as i64;
+    let digits = whole.abs().to_string();
+    let mut grouped = String::new();
+    for (idx, ch) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    let sign = if whole < 0 { "-" } else { "" };
+    format!("{}${}", sign, grouped)
+}
+
+fn run_and_report(
+    config: &Config,
+    http: &HttpClient,
+    resting: &mut Vec<RestingOrder>,
+) -> Result<()> {
+    let started = Instant::now();
+    let result = run_with_config(config, http, resting);
     if let Err(err) = &result {
         log_err!("Error: {}", err);
         for (idx, cause) in err.chain().skip(1).enumerate() {
@@ -26,17 +313,37 @@ fn main() -> Result<()> {
         }
     }
 
-    if let Ok(webhook) = std::env::var("SLACK_WEBHOOK_URL") {
+    let log = collected_log();
+    let idle = extract_opportunities(&log).is_none_or(|opps| opps == "0");
+    let quiet = config.slack_quiet_on_idle && idle && !log.contains("Error:");
+
+    let webhook = std::env::var("SLACK_WEBHOOK_URL").ok().filter(|_| !quiet);
+    if let Some(webhook) = webhook {
         let mode = if config.dry_run { "DRY_RUN" } else { "LIVE" };
         let now = chrono::Utc::now().to_rfc3339();
-        let log = collected_log();
         let mut header = format!("*Kalshi 15m bot run* `{}` `{}`", mode, now);
         if let Some(opps) = extract_opportunities(&log) {
             header.push_str(&format!("\nOpportunities: {}", opps));
         }
+        if let Some(skips) = extract_skip_summary(&log) {
+            header.push_str(&format!("\nSkips: {}", skips));
+        }
+        if let Some(deadline) = extract_deadline_notes(&log) {
+            header.push_str(&format!("\nDeadline: {}", deadline));
+        }
+        if let Some(alert) = result.as_ref().ok().and_then(|r| r.killed_alert.as_ref()) {
+            header.push_str(&format!("\nAlert: {}", alert));
+        }
+        if let Some(cex) = result.as_ref().ok().and_then(format_cex_summary) {
+            header.push_str(&format!("\nCEX: {}", cex));
+        }
         if log.contains("Error:") {
             header.push_str("\nResult: ERROR");
-            let error_lines = extract_error_lines(&log, 6);
+            let error_lines = extract_error_lines(
+                &log,
+                config.slack_error_lines,
+                config.slack_error_context_lines,
+            );
             if !error_lines.is_empty() {
                 header.push_str("\n\n*Error Details*");
                 for line in error_lines {
@@ -52,58 +359,100 @@ fn main() -> Result<()> {
             header.push_str("\n\n*Highlights*");
             header.push_str(&highlights);
         }
-        if let Err(err) = slack::post_run_log(&webhook, &header, None) {
+        header.push_str(&format!(
+            "\n\n{}",
+            format_footer(started, result.as_ref().ok())
+        ));
+        if let Err(err) = slack::post_run_log(http, &webhook, &header, None) {
             log_err!("Slack post failed: {}", err);
         }
     }
 
-    if let Err(err) = result {
-        return Err(err);
-    }
-    Ok(())
+    result.map(|_| ())
 }
 
-fn run_with_config(config: &Config) -> Result<()> {
+fn run_with_config(
+    config: &Config,
+    http: &HttpClient,
+    resting: &mut Vec<RestingOrder>,
+) -> Result<RunReport> {
+    if let Some(dir) = &config.replay_dir {
+        log_out!("Replaying recorded Kalshi responses from {:?}", dir);
+        let client = ReplayClient::new(config.clone(), http.clone(), dir.clone())?;
+        return run(client, config, http, resting);
+    }
+
     if config.dry_run {
         log_out!("Running in DRY_RUN mode.");
-        if !config.api_key.is_empty()
-            && (config.private_key_pem.is_some() || config.private_key_path.is_some())
-        {
-            let client = LiveClient::new(config.clone())?;
-            run(client, config)?;
-            return Ok(());
+        let has_credentials = config.has_credentials();
+        if has_credentials && config.dry_run_use_sandbox {
+            let sandbox_config = config.sandboxed();
+            log_out!(
+                "Routing DRY_RUN orders to sandbox {}{}",
+                sandbox_config.base_url,
+                sandbox_config.api_prefix
+            );
+            let client = LiveClient::new(sandbox_config.clone(), http.clone())?;
+            return run(client, &sandbox_config, http, resting);
+        }
+        if config.dry_run_use_sandbox {
+            log_err!("DRY_RUN_USE_SANDBOX set without credentials; using mock client.");
+        }
+        if has_credentials {
+            let client = LiveClient::new(config.clone(), http.clone())?;
+            return run(client, config, http, resting);
         }
 
         let client = MockClient::new(config.clone());
-        run(client, config)?;
-        return Ok(());
+        return run(client, config, http, resting);
     }
 
     if config.api_key.is_empty() {
         return Err(anyhow!("KALSHI_API_KEY not set"));
     }
 
-    let client = LiveClient::new(config.clone())?;
+    let client = LiveClient::new(config.clone(), http.clone())?;
 
     if config.check_exchange {
         if let Some(status) = client.exchange_status()? {
-            if !status.exchange_active || !status.trading_active {
+            let wait_secs = status
+                .exchange_estimated_resume_time
+                .map(|t| (t - client.now()).num_seconds())
+                .filter(|secs| (0..=config.resume_wait_secs).contains(secs));
+            let inactive = !status.exchange_active || !status.trading_active;
+            if let Some(secs) = wait_secs.filter(|_| inactive) {
+                log_err!(
+                    "Exchange inactive (exchange_active={}, trading_active={}); waiting {}s for scheduled resume.",
+                    status.exchange_active,
+                    status.trading_active,
+                    secs
+                );
+                std::thread::sleep(std::time::Duration::from_secs(secs as u64 + 1));
+                if let Some(status) = client.exchange_status()? {
+                    if !status.exchange_active || !status.trading_active {
+                        return Err(BotError::ExchangeInactive(format!(
+                            "still inactive after waiting {}s (exchange_active={}, trading_active={})",
+                            secs, status.exchange_active, status.trading_active
+                        ))
+                        .into());
+                    }
+                }
+                log_err!("Exchange resumed; continuing run.");
+            } else if inactive {
                 let resume = status
                     .exchange_estimated_resume_time
                     .map(|t| t.to_rfc3339())
                     .unwrap_or_else(|| "unknown".to_string());
-                return Err(anyhow!(
-                    "Exchange not active (exchange_active={}, trading_active={}). Resume: {}",
-                    status.exchange_active,
-                    status.trading_active,
-                    resume
-                ));
+                return Err(BotError::ExchangeInactive(format!(
+                    "exchange_active={}, trading_active={}. Resume: {}",
+                    status.exchange_active, status.trading_active, resume
+                ))
+                .into());
             }
         }
     }
 
-    run(client, config)?;
-    Ok(())
+    run(client, config, http, resting)
 }
 
 fn extract_opportunities(log: &str) -> Option<String> {
@@ -115,7 +464,21 @@ fn extract_opportunities(log: &str) -> Option<String> {
     None
 }
 
-fn extract_error_lines(log: &str, max_lines: usize) -> Vec<String> {
+fn extract_skip_summary(log: &str) -> Option<String> {
+    log.lines()
+        .find_map(|line| line.strip_prefix("Skip summary: "))
+        .map(|rest| rest.trim().to_string())
+}
+
+fn extract_deadline_notes(log: &str) -> Option<String> {
+    let notes: Vec<&str> = log
+        .lines()
+        .filter_map(|line| line.strip_prefix("Run deadline: "))
+        .collect();
+    (!notes.is_empty()).then(|| notes.join("; "))
+}
+
+fn extract_error_lines(log: &str, max_lines: usize, context_lines: usize) -> Vec<String> {
     let lines: Vec<&str> = log.lines().collect();
     let mut start_idx: Option<usize> = None;
     for (idx, line) in lines.iter().enumerate() {
@@ -128,8 +491,12 @@ fn extract_error_lines(log: &str, max_lines: usize) -> Vec<String> {
         return Vec::new();
     };
     let mut collected = Vec::new();
+    for line in &lines[start.saturating_sub(context_lines)..start] {
+        collected.push(format!("(context) {}", line.trim()));
+    }
+    let context_len = collected.len();
     for line in lines.iter().skip(start) {
-        if collected.is_empty() {
+        if collected.len() == context_len {
             collected.push((*line).to_string());
             continue;
         }
@@ -141,8 +508,8 @@ fn extract_error_lines(log: &str, max_lines: usize) -> Vec<String> {
         }
     }
 
-    if collected.len() > max_lines {
-        collected.truncate(max_lines);
+    if collected.len() - context_len > max_lines {
+        collected.truncate(context_len + max_lines);
         collected.push("...".to_string());
     }
 
@@ -150,132 +517,50 @@ fn extract_error_lines(log: &str, max_lines: usize) -> Vec<String> {
 }
 
 fn format_highlights(log: &str, max_items: usize) -> String {
-    let mut highlights = String::new();
-    let mut pending_title: Option<String> = None;
-    let mut pending_ticker: Option<String> = None;
-    let mut pending_yes: Option<String> = None;
-    let mut pending_no: Option<String> = None;
-    let mut pending_ttl: Option<i64> = None;
-    let mut count = 0;
+    let verdicts = log
+        .lines()
+        .filter_map(|line| line.strip_prefix("VERDICT "))
+        .filter_map(|rest| {
+            let mut parts = rest.split_whitespace();
+            let ticker = parts.next()?;
+            let verdict = parts.next()?;
+            let fields = parts
+                .filter_map(|part| part.split_once('='))
+                .filter(|(_, value)| *value != "-")
+                .collect::<std::collections::HashMap<_, _>>();
+            Some((ticker, verdict, fields))
+        })
+        .take(max_items);
 
-    for line in log.lines() {
-        if let Some(rest) = line.strip_prefix("Evaluating market ") {
-            let mut parts = rest.split(" | ");
-            if let Some(ticker) = parts.next() {
-                pending_ticker = Some(ticker.trim().to_string());
-            }
-            if let Some(details) = parts.next() {
-                if let Some(title) = extract_between(details, "title='", "'") {
-                    pending_title = Some(title.to_string());
-                }
-                if let Some(ttl) = extract_value(details, "ttl=") {
-                    let cleaned = ttl.trim_end_matches('s');
-                    if let Ok(value) = cleaned.parse::<i64>() {
-                        pending_ttl = Some(value);
-                    }
-                }
-                if let Some(yes) = extract_value(details, "yes=") {
-                    pending_yes = Some(yes.to_string());
-                }
-                if let Some(no) = extract_value(details, "no=") {
-                    pending_no = Some(no.to_string());
-                }
-            }
-            continue;
+    let mut highlights = String::new();
+    for (ticker, verdict, fields) in verdicts {
+        let mut info = Vec::new();
+        if let (Some(yes), Some(no)) = (fields.get("yes"), fields.get("no")) {
+            info.push(format!("YES {} / NO {}", yes, no));
         }
-
-        if let Some(skip) = line.strip_prefix("  -> skip: ") {
-            if let (Some(title), Some(ticker)) = (pending_title.take(), pending_ticker.take()) {
-                let reason = if let Some(combined) = extract_between(skip, "combined ", " >=") {
-                    format!("combined {}", combined.trim())
-                } else {
-                    skip.trim().to_string()
-                };
-                let price_part = match (pending_yes.take(), pending_no.take()) {
-                    (Some(yes), Some(no)) => format!("YES {} / NO {}", yes, no),
-                    _ => String::new(),
-                };
-                let ttl_part = pending_ttl.take().map(format_ttl);
-                let mut parts = Vec::new();
-                if !price_part.is_empty() {
-                    parts.push(price_part);
-                }
-                if let Some(ttl) = ttl_part {
-                    parts.push(ttl);
-                }
-                let info = if parts.is_empty() {
-                    String::new()
-                } else {
-                    parts.join(" — ")
-                };
-                if info.is_empty() {
-                    highlights.push_str(&format!("\n- *{}* ({}) — *{}*", title, ticker, reason));
-                } else {
-                    highlights.push_str(&format!(
-                        "\n- *{}* ({}) — {} — *{}*",
-                        title, ticker, info, reason
-                    ));
-                }
-                count += 1;
-                if count >= max_items {
-                    break;
-                }
-            }
-            continue;
+        if let Some(ttl) = fields.get("ttl").and_then(|ttl| ttl.parse().ok()) {
+            info.push(format_ttl(ttl));
         }
-
-        if let Some(ok) = line.strip_prefix("  -> QUALIFY: ") {
-            if let (Some(title), Some(ticker)) = (pending_title.take(), pending_ticker.take()) {
-                let reason = ok.trim();
-                let price_part = match (pending_yes.take(), pending_no.take()) {
-                    (Some(yes), Some(no)) => format!("YES {} / NO {}", yes, no),
-                    _ => String::new(),
-                };
-                let ttl_part = pending_ttl.take().map(format_ttl);
-                let mut parts = Vec::new();
-                if !price_part.is_empty() {
-                    parts.push(price_part);
-                }
-                if let Some(ttl) = ttl_part {
-                    parts.push(ttl);
-                }
-                let info = if parts.is_empty() {
-                    String::new()
-                } else {
-                    parts.join(" — ")
-                };
-                if info.is_empty() {
-                    highlights.push_str(&format!("\n- *{}* ({}) — *{}*", title, ticker, reason));
-                } else {
-                    highlights.push_str(&format!(
-                        "\n- *{}* ({}) — {} — *{}*",
-                        title, ticker, info, reason
-                    ));
-                }
-                count += 1;
-                if count >= max_items {
-                    break;
-                }
-            }
+        let outcome = format!(
+            "{} {}",
+            verdict.to_lowercase(),
+            fields.get("reason").copied().unwrap_or("unknown")
+        );
+        if info.is_empty() {
+            highlights.push_str(&format!("\n- *{}* — *{}*", ticker, outcome));
+        } else {
+            highlights.push_str(&format!(
+                "\n- *{}* — {} — *{}*",
+                ticker,
+                info.join(" — "),
+                outcome
+            ));
         }
     }
 
     highlights
 }
 
-fn extract_between<'a>(value: &'a str, start: &str, end: &str) -> Option<&'a str> {
-    let start_idx = value.find(start)? + start.len();
-    let rest = &value[start_idx..];
-    let end_idx = rest.find(end)?;
-    Some(&rest[..end_idx])
-}
-
-fn extract_value<'a>(value: &'a str, label: &str) -> Option<&'a str> {
-    let start_idx = value.find(label)? + label.len();
-    let rest = &value[start_idx..];
-    rest.split_whitespace().next()
-}
-
 fn format_ttl(seconds: i64) -> String {
     let mut value = seconds;
     if value < 0 {
@@ -286,10 +571,71 @@ fn format_ttl(seconds: i64) -> String {
     format!("TTL {}m{:02}s", minutes, secs)
 }
 
-fn run<C: KalshiClient>(client: C, config: &Config) -> Result<()> {
+fn run<C: KalshiClient>(
+    client: C,
+    config: &Config,
+    http: &HttpClient,
+    resting: &mut Vec<RestingOrder>,
+) -> Result<RunReport> {
+    let started = Instant::now();
     let now = client.now();
-    let cex_refs = if config.enable_cex_lag_scan {
-        match scan_btc_eth_references(config.cex_lag_min_sources) {
+    if !resting.is_empty() {
+        cancel_expiring_orders(&client, config, now, resting);
+    }
+    log_err!("Fetching markets...");
+    let mut markets = client.list_markets()?;
+    let fetched = markets.len();
+
+    if config.close_within_secs > 0 {
+        let cutoff = client.now() + chrono::Duration::seconds(config.close_within_secs);
+        let before = markets.len();
+        markets.retain(|market| market.close_time.is_none_or(|close| close <= cutoff));
+        log_err!(
+            "Close window {}s filtered out {} of {} markets",
+            config.close_within_secs,
+            before - markets.len(),
+            before
+        );
+    }
+
+    if config.max_amend_cents > 0 && !resting.is_empty() {
+        amend_resting_orders(&client, config, &markets, resting);
+    }
+
+    if markets.is_empty() {
+        log_err!("No markets loaded.");
+        return Ok(RunReport {
+            cex_refs: None,
+            markets: fetched,
+            requests: client.request_count(),
+            halted: None,
+            killed_alert: None,
+        });
+    }
+
+    if config.shuffle_markets {
+        let seed = config.shuffle_seed.unwrap_or_else(rand::random);
+        log_err!("Shuffling {} markets with seed {}", markets.len(), seed);
+        markets.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+
+    let mut cex_assets = markets
+        .iter()
+        .filter_map(|m| m.primary_asset())
+        .filter(|asset| *asset == "BTC" || *asset == "ETH")
+        .collect::<Vec<_>>();
+    cex_assets.sort();
+    cex_assets.dedup();
+    if config.enable_cex_lag_scan && cex_assets.is_empty() {
+        log_err!("Skipping CEX scan: no BTC/ETH markets in scope.");
+    }
+
+    let cex_refs = if config.enable_cex_lag_scan && !cex_assets.is_empty() {
+        let scanned = match cex::refreshed_references(config, &cex_assets) {
+            Some(map) => Ok(map),
+            None => scan_btc_eth_references(config, http, &cex_assets),
+        };
+        match scanned {
             Ok(map) => {
                 for reference in map.values() {
                     let venues = reference
@@ -299,11 +645,12 @@ fn run<C: KalshiClient>(client: C, config: &Config) -> Result<()> {
                         .collect::<Vec<_>>()
                         .join(", ");
                     log_err!(
-                        "CEX ref {} {:.2} from {} venues [{}]",
+                        "CEX ref {} {:.2} from {} venues [{}] confidence {:.2}",
                         reference.asset,
                         reference.reference_price,
                         reference.quotes.len(),
-                        venues
+                        venues,
+                        reference.confidence
                     );
                 }
                 Some(map)
@@ -317,23 +664,98 @@ fn run<C: KalshiClient>(client: C, config: &Config) -> Result<()> {
         None
     };
 
-    log_err!("Fetching markets...");
-    let markets = client.list_markets()?;
-
-    if markets.is_empty() {
-        log_err!("No markets loaded.");
-        return Ok(());
+    if let Some(refs) = &cex_refs {
+        compare_index(&client, config, refs);
     }
+    let mut report = RunReport {
+        cex_refs: cex_refs.clone(),
+        markets: fetched,
+        requests: 0,
+        halted: None,
+        killed_alert: None,
+    };
+
+    let positions = if config.net_positions {
+        client
+            .list_positions()
+            .context("failed to load positions for netting")?
+            .into_iter()
+            .filter(|p| p.position != 0)
+            .map(|p| (p.ticker, p.position))
+            .collect()
+    } else {
+        std::collections::HashMap::new()
+    };
 
-    let decisions = strategy::pick_opportunities(config, now, markets, cex_refs.as_ref());
+    let decisions =
+        strategy::pick_opportunities(config, now, markets, cex_refs.as_ref(), &positions);
     log_err!("Opportunities found: {}", decisions.len());
 
+    if let Some(path) = &config.decisions_json_path {
+        let json =
+            serde_json::to_string_pretty(&decisions).context("failed to serialize decisions")?;
+        std::fs::write(path, json)
+            .with_context(|| format!("failed to write decisions to {:?}", path))?;
+    }
+
+    if let Some(url) = &config.opportunity_webhook_url {
+        for decision in &decisions {
+            let signal = decision.lag_signal.as_ref();
+            let event = slack::OpportunityEvent {
+                ticker: &decision.market.ticker,
+                asset: decision.market.primary_asset(),
+                yes: decision.market.yes_ask_price(),
+                no: decision.market.no_ask_price(),
+                combined: decision.combined,
+                ttl_secs: (decision.close_time - now).num_seconds(),
+                model_yes_prob: signal.map(|s| s.model_yes_prob),
+                lag: signal.map(|s| s.lag),
+                verdict: decision.verdict,
+            };
+            if let Err(err) = slack::post_opportunity_event(http, url, &event) {
+                log_err!(
+                    "Opportunity webhook failed for {}: {}",
+                    decision.market.ticker,
+                    err
+                );
+            }
+        }
+    }
+
+    let (decisions, dropped) = strategy::apply_order_limits(config, decisions);
+    if dropped > 0 {
+        log_err!(
+            "Order limits dropped {} orders (max_per_run={}, max_per_market={})",
+            dropped,
+            config.max_orders_per_run,
+            config.max_orders_per_market
+        );
+    }
+
     if decisions.is_empty() {
         log_out!("No qualifying opportunities.");
-        return Ok(());
+        report.requests = client.request_count();
+        return Ok(report);
+    }
+
+    let mut decisions = decisions;
+    if config.watch && config.submit_offset_secs > 0 {
+        decisions.sort_by_key(|d| (d.fast, d.fast.then_some(d.close_time)));
     }
 
-    for decision in decisions {
+    let mut consecutive_errors = 0usize;
+    let mut killed_fast = 0usize;
+    let total = decisions.len();
+    'decisions: for (idx, decision) in decisions.into_iter().enumerate() {
+        if let Some(deadline) = config.run_deadline().filter(|d| started.elapsed() >= *d) {
+            log_err!(
+                "Run deadline: {}s exceeded; skipped {} of {} decisions",
+                deadline.as_secs(),
+                total - idx,
+                total
+            );
+            break;
+        }
         if config.dry_run {
             log_out!(
                 "DRY_RUN: {} -> {} orders ({})",
@@ -341,14 +763,431 @@ fn run<C: KalshiClient>(client: C, config: &Config) -> Result<()> {
                 decision.orders.len(),
                 decision.reason
             );
+            dry_run_check(config, &decision);
             continue;
         }
 
+        if !config.is_live_asset(decision.market.primary_asset()) {
+            log_out!(
+                "OBSERVE: {} -> {} orders ({})",
+                decision.market.ticker,
+                decision.orders.len(),
+                decision.reason
+            );
+            continue;
+        }
+
+        let mut decision = decision;
+        if decision.fast && config.watch && config.submit_offset_secs > 0 {
+            match wait_for_submit(
+                &client,
+                config,
+                &decision,
+                started,
+                cex_refs.as_ref(),
+                &positions,
+            ) {
+                Some(refreshed) => decision = refreshed,
+                None => continue,
+            }
+        }
+
+        if let Err(err) = strategy::validate_decision(config, &mut decision) {
+            log_err!("Decision skipped for {}: {}", decision.market.ticker, err);
+            continue;
+        }
+
+        let multi_leg = decision.orders.len() > 1;
+        let unwind = multi_leg && config.unwind_on_leg_failure;
+        let mut placed: Vec<(models::OrderRequest, models::OrderResponse)> = Vec::new();
         for order in decision.orders {
-            let response = client.place_order(&order)?;
-            log_out!("ORDER: {} -> {}", order.ticker, response.order_id);
+            let response = match client.place_order(&order) {
+                Ok(response) => {
+                    consecutive_errors = 0;
+                    response
+                }
+                Err(err) => {
+                    consecutive_errors += 1;
+                    log_err!("Order failed for {}: {}", order.ticker, err);
+                    if unwind && !placed.is_empty() {
+                        unwind_legs(&client, http, &placed, resting, &err);
+                    }
+                    if config.max_consecutive_order_errors > 0
+                        && consecutive_errors >= config.max_consecutive_order_errors
+                    {
+                        let halted = format!(
+                            "circuit breaker tripped after {} consecutive order errors",
+                            consecutive_errors
+                        );
+                        log_err!("WARN: {}; halting placements (last: {})", halted, err);
+                        report.halted = Some(halted);
+                        break 'decisions;
+                    }
+                    if unwind {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            log_out!(
+                "ORDER: {} -> {} status={} filled={} client_order_id={}",
+                order.ticker,
+                response.order_id,
+                response.status.as_deref().unwrap_or("unknown"),
+                response
+                    .filled_count
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                response.client_order_id.as_deref().unwrap_or("-")
+            );
+            if let Some(url) = &config.order_webhook_url {
+                let side = match order.side {
+                    models::Side::Yes => "yes",
+                    models::Side::No => "no",
+                };
+                let event = slack::OrderEvent {
+                    ticker: &order.ticker,
+                    side,
+                    price: order.price_dollars,
+                    qty: order.quantity,
+                    order_id: &response.order_id,
+                    client_order_id: response.client_order_id.as_deref(),
+                };
+                if let Err(err) = slack::post_order_event(http, url, &event) {
+                    log_err!("Order webhook failed: {}", err);
+                }
+            }
+            if config.resting_mode && response.status.as_deref() != Some("executed") {
+                resting.push(RestingOrder {
+                    order_id: response.order_id.clone(),
+                    ticker: order.ticker.clone(),
+                    side: order.side.clone(),
+                    quantity: order.quantity,
+                    price_dollars: order.price_dollars,
+                    close_time: decision.close_time,
+                });
+            }
+            let outcome = classify_fill(&response, order.quantity);
+            log_out!(
+                "FILL: {} -> {} {} filled={} remaining={}",
+                order.ticker,
+                response.order_id,
+                outcome,
+                response
+                    .filled_count
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                response
+                    .remaining_count
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            );
+            if outcome == "KILLED" && config.time_in_force == "fill_or_kill" {
+                log_err!(
+                    "WARN: fill_or_kill order {} on {} filled 0 contracts",
+                    response.order_id,
+                    order.ticker
+                );
+            }
+            if outcome == "KILLED" && decision.fast {
+                killed_fast += 1;
+                if report.killed_alert.is_none() && killed_fast >= config.killed_alert_threshold {
+                    log_err!("WARN: {} fast-band orders KILLED this run", killed_fast);
+                    report.killed_alert = Some(format!(
+                        "{} fast-band orders were KILLED this run (latest {}); fills are failing",
+                        killed_fast, order.ticker
+                    ));
+                }
+            }
+            let later_leg = !placed.is_empty();
+            let leg_failed =
+                multi_leg && (outcome == "KILLED" || (outcome == "PARTIAL" && later_leg));
+            let cause = anyhow!("{} {:?} leg was {}", order.ticker, order.side, outcome);
+            if outcome != "KILLED" {
+                placed.push((order, response));
+            }
+            if leg_failed && unwind {
+                if !placed.is_empty() {
+                    unwind_legs(&client, http, &placed, resting, &cause);
+                }
+                break;
+            }
+        }
+    }
+
+    if config.reconcile && !config.dry_run {
+        reconcile(&client)?;
+    }
+
+    report.requests = client.request_count();
+    Ok(report)
+}
+
+fn wait_for_submit<C: KalshiClient>(
+    client: &C,
+    config: &Config,
+    decision: &strategy::Decision,
+    started: Instant,
+    cex_refs: Option<&std::collections::HashMap<String, cex::AssetReference>>,
+    positions: &std::collections::HashMap<String, i64>,
+) -> Option<strategy::Decision> {
+    let ticker = &decision.market.ticker;
+    let target = decision.close_time - chrono::Duration::seconds(config.submit_offset_secs);
+    if let Ok(wait) = (target - client.now()).to_std() {
+        std::thread::sleep(wait);
+    }
+    let actual = client.now();
+    log_out!(
+        "TIMING: {} target {} actual {} ({:+}ms)",
+        ticker,
+        target.to_rfc3339(),
+        actual.to_rfc3339(),
+        (actual - target).num_milliseconds()
+    );
+
+    if let Some(deadline) = config.run_deadline().filter(|d| started.elapsed() >= *d) {
+        log_err!(
+            "Run deadline: {}s exceeded while holding {}; skipped",
+            deadline.as_secs(),
+            ticker
+        );
+        return None;
+    }
+
+    let market = match client.get_market(ticker) {
+        Ok(market) => market,
+        Err(err) => {
+            log_err!("Submit skipped for {}: refresh failed: {}", ticker, err);
+            return None;
+        }
+    };
+    let refreshed = strategy::pick_opportunities(config, actual, vec![market], cex_refs, positions)
+        .into_iter()
+        .next();
+    let refreshed = match refreshed.map(|fresh| strategy::clip_refreshed(decision, fresh)) {
+        Some(Ok(fresh)) => Some(fresh),
+        Some(Err(err)) => {
+            log_err!("Submit skipped for {}: {}", ticker, err);
+            return None;
+        }
+        None => None,
+    };
+    match &refreshed {
+        Some(fresh) if fresh.combined != decision.combined => log_err!(
+            "Submit repriced {}: combined {:.4} -> {:.4}",
+            ticker,
+            decision.combined,
+            fresh.combined
+        ),
+        Some(_) => {}
+        None => log_err!("Submit skipped for {}: no longer qualifies", ticker),
+    }
+    refreshed
+}
+
+fn dry_run_check(config: &Config, decision: &strategy::Decision) {
+    let mut checked = decision.clone();
+    if let Err(err) = strategy::validate_decision(config, &mut checked) {
+        log_out!(
+            "DRY_RUN WOULD-REJECT: {} decision skipped, {}",
+            decision.market.ticker,
+            err
+        );
+    }
+}
+
+fn classify_fill(response: &models::OrderResponse, quantity: i64) -> &'static str {
+    let resting = response.status.as_deref() == Some("resting");
+    match (response.filled_count, response.remaining_count) {
+        (Some(0), _) if resting => "RESTING",
+        (Some(0), _) => "KILLED",
+        (Some(filled), _) if filled >= quantity => "FILLED",
+        (Some(_), Some(0)) => "FILLED",
+        (Some(_), _) => "PARTIAL",
+        (None, _) if resting => "RESTING",
+        (None, _) if response.status.as_deref() == Some("canceled") => "KILLED",
+        (None, _) => "UNKNOWN",
+    }
+}
+
+fn compare_index<C: KalshiClient>(
+    client: &C,
+    config: &Config,
+    refs: &std::collections::HashMap<String, cex::AssetReference>,
+) {
+    for reference in refs.values() {
+        let index = match client.get_index(&reference.asset) {
+            Ok(Some(index)) if index > 0.0 => index,
+            Ok(_) => continue,
+            Err(err) => {
+                log_err!("Kalshi index {} fetch failed: {}", reference.asset, err);
+                continue;
+            }
+        };
+        let delta_pct = (reference.reference_price - index) / index * 100.0;
+        log_err!(
+            "Kalshi index {} {:.2} vs CEX ref {:.2} (delta {:+.3}%)",
+            reference.asset,
+            index,
+            reference.reference_price,
+            delta_pct
+        );
+        if delta_pct.abs() > config.index_delta_alert_pct {
+            log_err!(
+                "WARN: CEX ref for {} deviates {:+.3}% from Kalshi index (> {:.3}%)",
+                reference.asset,
+                delta_pct,
+                config.index_delta_alert_pct
+            );
+        }
+    }
+}
+
+fn unwind_legs<C: KalshiClient>(
+    client: &C,
+    http: &HttpClient,
+    placed: &[(models::OrderRequest, models::OrderResponse)],
+    resting: &mut Vec<RestingOrder>,
+    cause: &anyhow::Error,
+) {
+    let mut actions = Vec::new();
+    for (order, response) in placed {
+        let executed = response.status.as_deref() == Some("executed");
+        let filled = response
+            .filled_count
+            .unwrap_or(if executed { order.quantity } else { 0 });
+        if !executed {
+            match client.cancel_order(&response.order_id) {
+                Ok(()) => {
+                    resting.retain(|r| r.order_id != response.order_id);
+                    actions.push(format!("cancelled {} {}", order.ticker, response.order_id));
+                }
+                Err(err) => actions.push(format!(
+                    "cancel FAILED {} {}: {}",
+                    order.ticker, response.order_id, err
+                )),
+            }
+        }
+        if filled > 0 {
+            let exit = models::OrderRequest {
+                ticker: order.ticker.clone(),
+                side: order.side.clone(),
+                action: models::Action::Sell,
+                order_type: models::OrderType::Market,
+                price_dollars: order.price_dollars,
+                quantity: filled,
+            };
+            match client.place_order(&exit) {
+                Ok(exit_response) => actions.push(format!(
+                    "sold {} {:?} x{} ({})",
+                    order.ticker, order.side, filled, exit_response.order_id
+                )),
+                Err(err) => actions.push(format!(
+                    "exit FAILED {} {:?} x{}: {}",
+                    order.ticker, order.side, filled, err
+                )),
+            }
+        }
+    }
+
+    for action in &actions {
+        log_err!("UNWIND: {}", action);
+    }
+    if let Ok(webhook) = std::env::var("SLACK_WEBHOOK_URL") {
+        let mut header = format!("*Kalshi 15m bot leg failure* — later leg failed: {}", cause);
+        for action in &actions {
+            header.push_str("\n- ");
+            header.push_str(action);
+        }
+        if let Err(err) = slack::post_run_log(http, &webhook, &header, None) {
+            log_err!("Slack post failed: {}", err);
         }
     }
+}
+
+fn cancel_expiring_orders<C: KalshiClient>(
+    client: &C,
+    config: &Config,
+    now: chrono::DateTime<chrono::Utc>,
+    resting: &mut Vec<RestingOrder>,
+) {
+    resting.retain(|order| {
+        let seconds_to_close = (order.close_time - now).num_seconds();
+        if seconds_to_close >= config.cancel_before_close {
+            return true;
+        }
+        match client.cancel_order(&order.order_id) {
+            Ok(()) => log_out!(
+                "CANCEL: {} -> {} ({}s to close)",
+                order.ticker,
+                order.order_id,
+                seconds_to_close
+            ),
+            Err(err) => log_err!("Cancel failed for {}: {}", order.ticker, err),
+        }
+        false
+    });
+    log_err!("Resting orders tracked: {}", resting.len());
+}
+
+fn amend_resting_orders<C: KalshiClient>(
+    client: &C,
+    config: &Config,
+    markets: &[models::Market],
+    resting: &mut [RestingOrder],
+) {
+    for order in resting.iter_mut() {
+        let Some(market) = markets.iter().find(|m| m.ticker == order.ticker) else {
+            continue;
+        };
+        let ask = match order.side {
+            models::Side::Yes => market.yes_ask_price(),
+            models::Side::No => market.no_ask_price(),
+        };
+        let Some(ask) = ask.filter(|ask| *ask > order.price_dollars + 1e-9) else {
+            continue;
+        };
+        let step = config.max_amend_cents as f64 / 100.0;
+        let new_price = models::round_price(
+            (order.price_dollars + step).min(ask).min(0.99),
+            config.price_decimals,
+        );
+        if new_price <= order.price_dollars {
+            continue;
+        }
+        match client.amend_order(order, new_price) {
+            Ok(response) => {
+                log_out!(
+                    "AMEND: {} {:?} {:.2} -> {:.2} (ask {:.4}) -> {}",
+                    order.ticker,
+                    order.side,
+                    order.price_dollars,
+                    new_price,
+                    ask,
+                    response.order_id
+                );
+                order.order_id = response.order_id;
+                order.price_dollars = new_price;
+            }
+            Err(err) => log_err!("Amend failed for {}: {}", order.ticker, err),
+        }
+    }
+}
+
+fn reconcile<C: KalshiClient>(client: &C) -> Result<()> {
+    let positions = client.list_positions()?;
+    let open = positions.iter().filter(|p| p.position != 0).count();
+    log_err!(
+        "Reconcile: {} positions ({} non-zero)",
+        positions.len(),
+        open
+    );
+    for position in positions.iter().filter(|p| p.position != 0) {
+        log_err!("  position {} {}", position.ticker, position.position);
+    }
 
+    let fills = client.list_fills()?;
+    let contracts: i64 = fills.iter().map(|f| f.count).sum();
+    log_err!("Reconcile: {} fills ({} contracts)", fills.len(), contracts);
     Ok(())
 }
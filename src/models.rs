@@ -1,33 +1,149 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawMarket")]
 pub struct Market {
     pub ticker: String,
     pub title: String,
-    #[serde(default)]
     pub subtitle: Option<String>,
-    #[serde(default)]
+    pub yes_sub_title: Option<String>,
+    pub no_sub_title: Option<String>,
     pub event_ticker: Option<String>,
-    #[serde(default)]
     pub status: Option<String>,
-    pub close_time: DateTime<Utc>,
-    #[serde(default)]
+    pub close_time: Option<DateTime<Utc>>,
+    pub expiration_time: Option<DateTime<Utc>>,
     pub yes_ask_dollars: Option<String>,
-    #[serde(default)]
     pub no_ask_dollars: Option<String>,
+    pub yes_ask: Option<i64>,
+    pub no_ask: Option<i64>,
+    pub yes_bid_dollars: Option<String>,
+    pub no_bid_dollars: Option<String>,
+    pub yes_bid: Option<i64>,
+    pub no_bid: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
+struct RawMarket {
+    ticker: String,
+    title: String,
+    #[serde(default)]
+    subtitle: Option<String>,
+    #[serde(default)]
+    yes_sub_title: Option<String>,
+    #[serde(default)]
+    no_sub_title: Option<String>,
+    #[serde(default)]
+    event_ticker: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default, deserialize_with = "lenient_time")]
+    close_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "lenient_time")]
+    expiration_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "lenient_time")]
+    expected_expiration_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    yes_ask_dollars: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    no_ask_dollars: Option<String>,
+    #[serde(default)]
+    yes_ask: Option<i64>,
+    #[serde(default)]
+    no_ask: Option<i64>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    yes_bid_dollars: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    no_bid_dollars: Option<String>,
+    #[serde(default)]
+    yes_bid: Option<i64>,
+    #[serde(default)]
+    no_bid: Option<i64>,
+}
+
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(serde_json::Number),
+    }
+
+    Ok(
+        Option::<StringOrNumber>::deserialize(deserializer)?.map(|value| match value {
+            StringOrNumber::String(s) => s,
+            StringOrNumber::Number(n) => n.to_string(),
+        }),
+    )
+}
+
+fn lenient_time<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<serde_json::Value>::deserialize(deserializer)?
+        .and_then(|value| {
+            value
+                .as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        })
+        .map(|time| time.with_timezone(&Utc)))
+}
+
+impl From<RawMarket> for Market {
+    fn from(raw: RawMarket) -> Self {
+        let expiration_time = raw.expiration_time.or(raw.expected_expiration_time);
+        let close_time = raw.close_time.or(expiration_time);
+        Market {
+            ticker: raw.ticker,
+            title: raw.title,
+            subtitle: raw.subtitle,
+            yes_sub_title: raw.yes_sub_title,
+            no_sub_title: raw.no_sub_title,
+            event_ticker: raw.event_ticker,
+            status: raw.status,
+            close_time,
+            expiration_time,
+            yes_ask_dollars: raw.yes_ask_dollars,
+            no_ask_dollars: raw.no_ask_dollars,
+            yes_ask: raw.yes_ask,
+            no_ask: raw.no_ask,
+            yes_bid_dollars: raw.yes_bid_dollars,
+            no_bid_dollars: raw.no_bid_dollars,
+            yes_bid: raw.yes_bid,
+            no_bid: raw.no_bid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Side {
     Yes,
     No,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Action {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum OrderType {
+    Limit,
+    Market,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub ticker: String,
     pub side: Side,
+    pub action: Action,
+    pub order_type: OrderType,
     pub price_dollars: f64,
     pub quantity: i64,
 }
@@ -35,9 +151,54 @@ pub struct OrderRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResponse {
     pub order_id: String,
+    pub status: Option<String>,
+    pub filled_count: Option<i64>,
+    pub remaining_count: Option<i64>,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestingOrder {
+    pub order_id: String,
+    pub ticker: String,
+    pub side: Side,
+    pub quantity: i64,
+    pub price_dollars: f64,
+    pub close_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub ticker: String,
+    #[serde(default)]
+    pub position: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub ticker: String,
+    #[serde(default)]
+    pub side: Option<String>,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub count: i64,
 }
 
 impl Market {
+    pub fn yes_ask_price(&self) -> Option<f64> {
+        quote_price(self.yes_ask_dollars.as_deref(), self.yes_ask)
+    }
+
+    pub fn no_ask_price(&self) -> Option<f64> {
+        quote_price(self.no_ask_dollars.as_deref(), self.no_ask)
+    }
+
+    pub fn yes_bid_price(&self) -> Option<f64> {
+        quote_price(self.yes_bid_dollars.as_deref(), self.yes_bid)
+    }
+
     pub fn primary_asset(&self) -> Option<&'static str> {
         let haystack = self.haystack();
         if haystack.contains("btc") || haystack.contains("bitcoin") {
@@ -46,6 +207,9 @@ impl Market {
         if haystack.contains("eth") || haystack.contains("ethereum") {
             return Some("ETH");
         }
+        if haystack.contains("sol") || haystack.contains("solana") {
+            return Some("SOL");
+        }
         None
     }
 
@@ -54,29 +218,12 @@ impl Market {
         haystack.contains("btc") || haystack.contains("bitcoin")
     }
 
-    pub fn is_crypto_related(&self, assets: &[String]) -> bool {
-        let haystack = self.haystack();
-        if assets.is_empty() {
-            return false;
-        }
-        for asset in assets {
-            if asset.is_empty() {
-                continue;
-            }
-            if haystack.contains(asset) {
-                return true;
-            }
-            if asset == "btc" && haystack.contains("bitcoin") {
-                return true;
-            }
-            if asset == "eth" && haystack.contains("ethereum") {
-                return true;
-            }
-            if asset == "sol" && haystack.contains("solana") {
-                return true;
-            }
-        }
-        false
+    pub fn is_crypto_related(
+        &self,
+        assets: &[String],
+        synonyms: &HashMap<String, Vec<String>>,
+    ) -> bool {
+        mentions_asset(&self.haystack(), assets, synonyms)
     }
 
     fn haystack(&self) -> String {
@@ -93,3 +240,140 @@ impl Market {
         haystack.to_lowercase()
     }
 }
+
+fn quote_price(dollars: Option<&str>, cents: Option<i64>) -> Option<f64> {
+    if let Some(price) = dollars.and_then(|v| v.parse::<f64>().ok()) {
+        return Some(price);
+    }
+    cents.map(|c| c as f64 / 100.0)
+}
+
+pub fn round_price(price: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (price * scale).round() / scale
+}
+
+pub fn mentions_asset(
+    haystack: &str,
+    assets: &[String],
+    synonyms: &HashMap<String, Vec<String>>,
+) -> bool {
+    for asset in assets {
+        if asset.is_empty() {
+            continue;
+        }
+        if haystack.contains(asset.as_str()) {
+            return true;
+        }
+        if let Some(words) = synonyms.get(asset) {
+            if words
+                .iter()
+                .any(|w| !w.is_empty() && haystack.contains(w.as_str()))
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dollar_prices_accept_strings_and_numbers() {
+        let market: Market = serde_json::from_str(
+            r#"{
+                "ticker": "KXBTC15M-26JAN051415-15",
+                "title": "BTC price up in next 15 mins?",
+                "yes_ask_dollars": "0.4500",
+                "no_ask_dollars": 0.52,
+                "yes_bid_dollars": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(market.yes_ask_dollars.as_deref(), Some("0.4500"));
+        assert_eq!(market.no_ask_dollars.as_deref(), Some("0.52"));
+        assert_eq!(market.yes_ask_price(), Some(0.45));
+        assert_eq!(market.no_ask_price(), Some(0.52));
+        assert_eq!(market.yes_bid_price(), None);
+    }
+
+    #[test]
+    fn close_time_falls_back_to_expiration() {
+        let market: Market = serde_json::from_str(
+            r#"{
+                "ticker": "KXBTC15M-26JAN051415-15",
+                "title": "BTC price up in next 15 mins?",
+                "close_time": null,
+                "expiration_time": "2026-01-05T14:15:00Z"
+            }"#,
+        )
+        .unwrap();
+        let expected = "2026-01-05T14:15:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(market.close_time, Some(expected));
+        assert_eq!(market.expiration_time, Some(expected));
+
+        let market: Market = serde_json::from_str(
+            r#"{
+                "ticker": "KXBTC15M-26JAN051415-15",
+                "title": "BTC price up in next 15 mins?",
+                "expected_expiration_time": "2026-01-05T14:20:00Z"
+            }"#,
+        )
+        .unwrap();
+        let expected = "2026-01-05T14:20:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(market.close_time, Some(expected));
+    }
+
+    #[test]
+    fn realistic_market_payload_parses_leniently() {
+        let market: Market = serde_json::from_str(
+            r#"{
+                "ticker": "KXBTCD-26JAN0514-T97249.99",
+                "event_ticker": "KXBTCD-26JAN0514",
+                "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+                "subtitle": "",
+                "yes_sub_title": "$97,250 or above",
+                "no_sub_title": "$97,250 or above",
+                "status": "active",
+                "open_time": "2026-01-05T13:00:00Z",
+                "close_time": "2026-01-05T19:00:00.000Z",
+                "expiration_time": "2026-01-05T14:00:00-05:00",
+                "expected_expiration_time": 1767639600,
+                "yes_bid": 41,
+                "yes_ask": 44,
+                "no_bid": 56,
+                "no_ask": 59,
+                "yes_bid_dollars": "0.4100",
+                "yes_ask_dollars": 0.44,
+                "no_bid_dollars": null,
+                "no_ask_dollars": "0.5900",
+                "last_price_dollars": "0.4300",
+                "volume": 1520
+            }"#,
+        )
+        .unwrap();
+        let expected = "2026-01-05T19:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(market.close_time, Some(expected));
+        assert_eq!(market.expiration_time, Some(expected));
+        assert_eq!(market.yes_ask_price(), Some(0.44));
+        assert_eq!(market.no_ask_price(), Some(0.59));
+        assert_eq!(market.yes_bid_price(), Some(0.41));
+        assert_eq!(market.no_bid_dollars, None);
+        assert_eq!(market.yes_sub_title.as_deref(), Some("$97,250 or above"));
+
+        let market: Market = serde_json::from_str(
+            r#"{
+                "ticker": "KXBTCD-26JAN0514-T97249.99",
+                "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+                "close_time": "not a time",
+                "expiration_time": 1767639600
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(market.close_time, None);
+        assert_eq!(market.expiration_time, None);
+    }
+}
@@ -1,13 +1,46 @@
+use std::thread;
+use std::time::Duration;
+
 use anyhow::{anyhow, Result};
 use reqwest::blocking::Client;
 use serde::Serialize;
 
+const POST_ATTEMPTS: usize = 3;
+
 #[derive(Serialize)]
 struct SlackPayload<'a> {
     text: &'a str,
 }
 
-pub fn post_run_log(webhook_url: &str, header: &str, log: Option<&str>) -> Result<()> {
+#[derive(Serialize)]
+pub struct OrderEvent<'a> {
+    pub ticker: &'a str,
+    pub side: &'a str,
+    pub price: f64,
+    pub qty: i64,
+    pub order_id: &'a str,
+    pub client_order_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub struct OpportunityEvent<'a> {
+    pub ticker: &'a str,
+    pub asset: Option<&'a str>,
+    pub yes: Option<f64>,
+    pub no: Option<f64>,
+    pub combined: f64,
+    pub ttl_secs: i64,
+    pub model_yes_prob: Option<f64>,
+    pub lag: Option<f64>,
+    pub verdict: &'a str,
+}
+
+pub fn post_run_log(
+    http: &Client,
+    webhook_url: &str,
+    header: &str,
+    log: Option<&str>,
+) -> Result<()> {
     if webhook_url.trim().is_empty() {
         return Ok(());
     }
@@ -22,12 +55,47 @@ pub fn post_run_log(webhook_url: &str, header: &str, log: Option<&str>) -> Resul
     }
 
     let payload = SlackPayload { text: &text };
-    let client = Client::new();
-    let response = client.post(webhook_url).json(&payload).send()?;
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().unwrap_or_default();
-        return Err(anyhow!("slack webhook failed: {} - {}", status, body));
+    post_json_with_retry(http, webhook_url, &payload).map_err(|err| anyhow!("slack {}", err))
+}
+
+pub fn post_order_event(http: &Client, webhook_url: &str, event: &OrderEvent) -> Result<()> {
+    if webhook_url.trim().is_empty() {
+        return Ok(());
+    }
+    post_json_with_retry(http, webhook_url, event).map_err(|err| anyhow!("order {}", err))
+}
+
+pub fn post_opportunity_event(
+    http: &Client,
+    webhook_url: &str,
+    event: &OpportunityEvent,
+) -> Result<()> {
+    if webhook_url.trim().is_empty() {
+        return Ok(());
+    }
+    post_json_with_retry(http, webhook_url, event).map_err(|err| anyhow!("opportunity {}", err))
+}
+
+fn post_json_with_retry<T: Serialize>(client: &Client, url: &str, payload: &T) -> Result<()> {
+    let mut last_err = anyhow!("webhook not attempted");
+    for attempt in 1..=POST_ATTEMPTS {
+        match client.post(url).json(payload).send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().unwrap_or_default();
+                last_err = anyhow!("webhook failed: {} - {}", status, body);
+                if !status.is_server_error() && status.as_u16() != 429 {
+                    break;
+                }
+            }
+            Err(err) => {
+                last_err = anyhow!("webhook failed: {}", err);
+            }
+        }
+        if attempt < POST_ATTEMPTS {
+            thread::sleep(Duration::from_millis(500 * attempt as u64));
+        }
     }
-    Ok(())
+    Err(last_err)
 }
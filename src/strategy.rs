@@ -1,31 +1,53 @@
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use regex::Regex;
+use serde::Serialize;
 use std::collections::HashMap;
 
 use crate::cex::AssetReference;
-use crate::config::Config;
+use crate::config::{Config, FeeModel, TradeMode};
+use crate::fees;
 use crate::log_err;
-use crate::models::{Market, OrderRequest, Side};
+use crate::models::{round_price, Action, Market, OrderRequest, OrderType, Side};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Decision {
     pub market: Market,
+    pub close_time: DateTime<Utc>,
     pub orders: Vec<OrderRequest>,
     pub reason: String,
+    pub combined: f64,
+    pub lag_signal: Option<LagSignal>,
+    pub fast: bool,
+    pub verdict: &'static str,
 }
 
+/// Returns qualifying decisions ordered by descending CEX lag, then ascending
+/// combined price, then ticker, so identical inputs always produce the same
+/// order (and the same truncation under `max_decisions` and order caps). With
+/// `shuffle_markets`, ties keep the (shuffled) evaluation order instead of
+/// falling back to ticker.
 pub fn pick_opportunities(
     config: &Config,
     now: DateTime<Utc>,
     markets: Vec<Market>,
     cex_refs: Option<&HashMap<String, AssetReference>>,
+    positions: &HashMap<String, i64>,
 ) -> Vec<Decision> {
     let mut decisions = Vec::new();
+    let mut skips: HashMap<&str, usize> = HashMap::new();
     let interval_re = Regex::new(&config.interval_regex)
         .unwrap_or_else(|_| Regex::new("(?i)\\b15\\s?m(in(ute)?)?\\b").unwrap());
 
     for market in markets {
-        let seconds_to_close = (market.close_time - now).num_seconds();
+        let Some(close_time) = market.close_time else {
+            record_skip(config, &mut skips, &market, "missing-close-time", 0, None);
+            if config.log_decisions {
+                log_err!("  -> skip: {} missing close_time", market.ticker);
+            }
+            continue;
+        };
+        let seconds_to_close = (close_time - now).num_seconds();
         if config.log_decisions {
             log_err!(
                 "Evaluating market {} | title='{}' subtitle='{}' event='{}' close={} ttl={}s yes={} no={}",
@@ -33,50 +55,136 @@ pub fn pick_opportunities(
                 market.title,
                 market.subtitle.clone().unwrap_or_default(),
                 market.event_ticker.clone().unwrap_or_default(),
-                market.close_time,
+                close_time,
                 seconds_to_close,
-                market.yes_ask_dollars.clone().unwrap_or_default(),
-                market.no_ask_dollars.clone().unwrap_or_default()
+                market
+                    .yes_ask_price()
+                    .map(|p| format!("{:.4}", p))
+                    .unwrap_or_default(),
+                market
+                    .no_ask_price()
+                    .map(|p| format!("{:.4}", p))
+                    .unwrap_or_default()
             );
         }
-        if config.btc_only && !market.is_btc_related() {
+        let asset_filter = !config.disable_asset_filter;
+        if asset_filter && config.btc_only && !market.is_btc_related() {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "not-btc",
+                seconds_to_close,
+                None,
+            );
             if config.log_decisions {
                 log_err!("  -> skip: not BTC-related");
             }
             continue;
         }
-        if config.crypto_only && !market.is_crypto_related(&config.crypto_assets) {
+        if asset_filter
+            && config.crypto_only
+            && !market.is_crypto_related(&config.crypto_assets, &config.crypto_synonyms)
+        {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "not-crypto",
+                seconds_to_close,
+                None,
+            );
             if config.log_decisions {
                 log_err!("  -> skip: not crypto-related");
             }
             continue;
         }
-        if !matches_interval(&market, &interval_re) {
+        let Some(interval_field) =
+            matches_interval(&market, &interval_re, &config.interval_match_fields)
+        else {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "not-15m",
+                seconds_to_close,
+                None,
+            );
             if config.log_decisions {
                 log_err!("  -> skip: not 15-minute interval");
             }
             continue;
+        };
+        if config.log_decisions {
+            log_err!("  -> interval matched on {}", interval_field);
+        }
+
+        if let Some(status) = &market.status {
+            let status = status.to_lowercase();
+            if !config.allowed_statuses.is_empty() && !config.allowed_statuses.contains(&status) {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "bad-status",
+                    seconds_to_close,
+                    None,
+                );
+                if config.log_decisions {
+                    log_err!("  -> skip: market status '{}' not allowed", status);
+                }
+                continue;
+            }
         }
 
         if seconds_to_close < 0 {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "closed",
+                seconds_to_close,
+                None,
+            );
             if config.log_decisions {
                 log_err!("  -> skip: market already closed ({}s)", seconds_to_close);
             }
             continue;
         }
 
-        let yes_price = market
-            .yes_ask_dollars
-            .as_ref()
-            .and_then(|v| v.parse::<f64>().ok());
-        let no_price = market
-            .no_ask_dollars
-            .as_ref()
-            .and_then(|v| v.parse::<f64>().ok());
+        if seconds_to_close < config.min_seconds_to_close {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "too-close",
+                seconds_to_close,
+                None,
+            );
+            if config.log_decisions {
+                log_err!(
+                    "  -> skip: too close to close ({}s < {}s)",
+                    seconds_to_close,
+                    config.min_seconds_to_close
+                );
+            }
+            continue;
+        }
+
+        let yes_price = market.yes_ask_price();
+        let no_price = market.no_ask_price();
 
         let (yes_price, no_price) = match (yes_price, no_price) {
             (Some(yes), Some(no)) => (yes, no),
             _ => {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "missing-ask",
+                    seconds_to_close,
+                    None,
+                );
                 if config.log_decisions {
                     log_err!("  -> skip: missing or invalid YES/NO ask");
                 }
@@ -84,19 +192,97 @@ pub fn pick_opportunities(
             }
         };
 
+        let quantity = config.order_count_for(market.primary_asset());
         let combined = yes_price + no_price;
-        let yes_in_band = (0.90..=0.97).contains(&yes_price);
-        let no_in_band = (0.90..=0.97).contains(&no_price);
+        if combined < config.combined_min_price {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "combined-below-floor",
+                seconds_to_close,
+                None,
+            );
+            if config.log_decisions {
+                log_err!(
+                    "  -> skip: combined below floor ({:.4} < {:.4})",
+                    combined,
+                    config.combined_min_price
+                );
+            }
+            continue;
+        }
+        let (band_low, band_high) = config.fast_band_for(market.primary_asset());
+        let yes_in_band = (band_low..=band_high).contains(&yes_price);
+        let no_in_band = (band_low..=band_high).contains(&no_price);
         let price_in_band = yes_in_band || no_in_band;
         let qualifies_fast = seconds_to_close < 60 && price_in_band;
-        let lag_signal = compute_cex_lag_signal(config, &market, yes_price, cex_refs);
+        let kalshi_yes_prob = match market.yes_bid_price() {
+            Some(bid) if config.use_mid_for_signal && bid > 0.0 && bid <= yes_price => {
+                (bid + yes_price) / 2.0
+            }
+            _ => yes_price,
+        };
+        let lag_signal = compute_cex_lag_signal(config, &market, kalshi_yes_prob, cex_refs)
+            .filter(|signal| is_sane_lag(config, &market, signal));
+        if let Some(signal) = lag_signal
+            .as_ref()
+            .filter(|signal| signal.strike_distance_bps < config.min_strike_distance_bps)
+        {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "strike-too-close",
+                seconds_to_close,
+                Some(signal),
+            );
+            if config.log_decisions {
+                log_err!(
+                    "  -> skip: reference {:.2} is {:.1}bps from strike {:.2} (< {:.1}bps)",
+                    signal.reference_price,
+                    signal.strike_distance_bps,
+                    signal.strike,
+                    config.min_strike_distance_bps
+                );
+            }
+            continue;
+        }
 
-        if config.cex_lag_require_signal && config.enable_cex_lag_scan {
+        let require_signal = match config.trade_mode {
+            TradeMode::Aggressive => false,
+            TradeMode::Passive => config.enable_cex_lag_scan,
+            TradeMode::SignalOnly => true,
+        };
+        if require_signal {
             let has_signal = lag_signal
                 .as_ref()
                 .map(|signal| signal.abs_lag >= config.cex_lag_threshold)
                 .unwrap_or(false);
+            let is_equal = parse_direction(&market) == Some(Direction::Equal);
+            if !has_signal && is_equal && lag_signal.is_none() {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "equal-market",
+                    seconds_to_close,
+                    None,
+                );
+                if config.log_decisions {
+                    log_err!("  -> skip: equality market with no model probability");
+                }
+                continue;
+            }
             if !has_signal {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "lag-below-threshold",
+                    seconds_to_close,
+                    lag_signal.as_ref(),
+                );
                 if config.log_decisions {
                     log_err!(
                         "  -> skip: cex lag signal below threshold {:.4}",
@@ -107,57 +293,279 @@ pub fn pick_opportunities(
             }
         }
 
-        if !qualifies_fast && combined >= config.combined_max_price {
+        let (metric_name, metric_value, metric_limit) =
+            combined_metric(config, combined, seconds_to_close);
+        if config.log_decisions && config.combined_ttl_tightening > 0.0 {
+            log_err!(
+                "  -> THRESHOLD: {} ttl {}s effective {} limit {:.4}",
+                market.ticker,
+                seconds_to_close,
+                metric_name,
+                metric_limit
+            );
+        }
+        let signal_trade = !qualifies_fast
+            && metric_value >= metric_limit
+            && config.trade_mode == TradeMode::SignalOnly
+            && lag_signal.is_some();
+        if !qualifies_fast && !signal_trade && metric_value >= metric_limit {
+            record_skip(
+                config,
+                &mut skips,
+                &market,
+                "combined-too-high",
+                seconds_to_close,
+                lag_signal.as_ref(),
+            );
             if config.log_decisions {
                 log_err!(
-                    "  -> skip: combined {:.4} >= threshold {:.4}",
-                    combined,
-                    config.combined_max_price
+                    "  -> skip: {} {:.4} >= threshold {:.4}",
+                    metric_name,
+                    metric_value,
+                    metric_limit
                 );
             }
             continue;
         }
 
-        let orders = if qualifies_fast {
+        let final_basket = qualifies_fast
+            && seconds_to_close < config.final_secs
+            && combined < config.final_combined_max;
+        let mut yes_fast = yes_in_band || final_basket;
+        let mut no_fast = no_in_band || final_basket;
+        if final_basket {
+            if config.log_decisions {
+                log_err!(
+                    "  -> FINAL: {} ttl {}s combined {:.4} < {:.4}; buying both legs",
+                    market.ticker,
+                    seconds_to_close,
+                    combined,
+                    config.final_combined_max
+                );
+            }
+        } else if qualifies_fast && config.respect_model_in_fast_band {
+            if let Some(signal) = &lag_signal {
+                if yes_fast && signal.model_yes_prob < yes_price {
+                    yes_fast = false;
+                    if config.log_decisions {
+                        log_err!(
+                            "  -> VETO: {} YES ask {:.4} above model_yes {:.3}",
+                            market.ticker,
+                            yes_price,
+                            signal.model_yes_prob
+                        );
+                    }
+                }
+                if no_fast && signal.model_no_prob < no_price {
+                    no_fast = false;
+                    if config.log_decisions {
+                        log_err!(
+                            "  -> VETO: {} NO ask {:.4} above model_no {:.3}",
+                            market.ticker,
+                            no_price,
+                            signal.model_no_prob
+                        );
+                    }
+                }
+                if !yes_fast && !no_fast {
+                    record_skip(
+                        config,
+                        &mut skips,
+                        &market,
+                        "model-veto",
+                        seconds_to_close,
+                        lag_signal.as_ref(),
+                    );
+                    continue;
+                }
+            }
+        }
+
+        let mut orders = if qualifies_fast {
+            let fast_type = if config.fast_use_market {
+                OrderType::Market
+            } else {
+                OrderType::Limit
+            };
             let mut fast_orders = Vec::new();
-            if yes_in_band {
+            if yes_fast {
                 fast_orders.push(OrderRequest {
                     ticker: market.ticker.clone(),
                     side: Side::Yes,
+                    action: Action::Buy,
+                    order_type: fast_type,
                     price_dollars: yes_price,
-                    quantity: config.order_count,
+                    quantity,
                 });
             }
-            if no_in_band {
+            if no_fast {
                 fast_orders.push(OrderRequest {
                     ticker: market.ticker.clone(),
                     side: Side::No,
+                    action: Action::Buy,
+                    order_type: fast_type,
                     price_dollars: no_price,
-                    quantity: config.order_count,
+                    quantity,
                 });
             }
             fast_orders
+        } else if let Some(signal) = lag_signal.as_ref().filter(|_| signal_trade) {
+            let (side, price) = if signal.lag > 0.0 {
+                (Side::Yes, yes_price)
+            } else {
+                (Side::No, no_price)
+            };
+            vec![OrderRequest {
+                ticker: market.ticker.clone(),
+                side,
+                action: Action::Buy,
+                order_type: OrderType::Limit,
+                price_dollars: price,
+                quantity,
+            }]
+        } else if config.single_leg_mode {
+            let (side, price) = if yes_price <= no_price {
+                (Side::Yes, yes_price)
+            } else {
+                (Side::No, no_price)
+            };
+            vec![OrderRequest {
+                ticker: market.ticker.clone(),
+                side,
+                action: Action::Buy,
+                order_type: OrderType::Limit,
+                price_dollars: price,
+                quantity,
+            }]
         } else {
             vec![
                 OrderRequest {
                     ticker: market.ticker.clone(),
                     side: Side::Yes,
+                    action: Action::Buy,
+                    order_type: OrderType::Limit,
                     price_dollars: yes_price,
-                    quantity: config.order_count,
+                    quantity,
                 },
                 OrderRequest {
                     ticker: market.ticker.clone(),
                     side: Side::No,
+                    action: Action::Buy,
+                    order_type: OrderType::Limit,
                     price_dollars: no_price,
-                    quantity: config.order_count,
+                    quantity,
                 },
             ]
         };
 
-        let mut reason = if qualifies_fast {
+        let has_yes = orders.iter().any(|o| matches!(o.side, Side::Yes));
+        let has_no = orders.iter().any(|o| matches!(o.side, Side::No));
+        if has_yes && has_no && combined >= 1.0 {
+            let keep_yes = yes_price <= no_price;
+            orders.retain(|o| matches!(o.side, Side::Yes) == keep_yes);
+            if config.log_decisions {
+                log_err!(
+                    "  -> CORRECTION: {} would buy YES {:.4} + NO {:.4} = {:.4} >= 1.0; keeping {} only",
+                    market.ticker,
+                    yes_price,
+                    no_price,
+                    combined,
+                    if keep_yes { "YES" } else { "NO" }
+                );
+            }
+        }
+
+        let basket = orders.len() > 1;
+        if basket && config.fee_model != FeeModel::None {
+            let fees = fees::fee_for(config.fee_model, yes_price, quantity)
+                + fees::fee_for(config.fee_model, no_price, quantity);
+            let all_in = combined + fees / quantity.max(1) as f64;
+            if all_in >= 1.0 {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "fees-exceed-edge",
+                    seconds_to_close,
+                    lag_signal.as_ref(),
+                );
+                if config.log_decisions {
+                    log_err!(
+                        "  -> skip: combined {:.4} + fees {:.4}/contract = {:.4} >= 1.0",
+                        combined,
+                        fees / quantity.max(1) as f64,
+                        all_in
+                    );
+                }
+                continue;
+            }
+        }
+
+        let position = positions.get(&market.ticker).copied().unwrap_or(0);
+        if position != 0 {
+            let before = orders.len();
+            orders.retain(|o| !crosses_position(o, position));
+            if config.log_decisions && orders.len() < before {
+                log_err!(
+                    "  -> NETTING: {} holds {} {}; dropped {} crossing order(s)",
+                    market.ticker,
+                    position.abs(),
+                    if position > 0 { "YES" } else { "NO" },
+                    before - orders.len()
+                );
+            }
+            if orders.is_empty() {
+                record_skip(
+                    config,
+                    &mut skips,
+                    &market,
+                    "position-netting",
+                    seconds_to_close,
+                    lag_signal.as_ref(),
+                );
+                continue;
+            }
+        }
+
+        if config.price_improve_cents > 0 {
+            for order in orders.iter_mut() {
+                if !matches!(order.action, Action::Buy)
+                    || !matches!(order.order_type, OrderType::Limit)
+                {
+                    continue;
+                }
+                let ask = order.price_dollars;
+                order.price_dollars = improve_price(ask, config.price_improve_cents);
+                if config.log_decisions {
+                    log_err!(
+                        "  -> PRICE: {:?} ask {:.4} -> limit {:.4}",
+                        order.side,
+                        ask,
+                        order.price_dollars
+                    );
+                }
+            }
+        }
+
+        let mut reason = if final_basket {
+            format!(
+                "TTL {}s with YES {:.4} + NO {:.4} = {:.4} < {:.4} (final-seconds basket)",
+                seconds_to_close, yes_price, no_price, combined, config.final_combined_max
+            )
+        } else if qualifies_fast {
             format!(
-                "TTL {}s with YES {:.4} / NO {:.4} in 0.90-0.97 band (single-side)",
-                seconds_to_close, yes_price, no_price
+                "TTL {}s with YES {:.4} / NO {:.4} in {:.2}-{:.2} band (single-side)",
+                seconds_to_close, yes_price, no_price, band_low, band_high
+            )
+        } else if signal_trade {
+            format!(
+                "YES {:.4} / NO {:.4} within {}s of close (signal-only, {} {:.4} >= {:.4})",
+                yes_price, no_price, seconds_to_close, metric_name, metric_value, metric_limit
+            )
+        } else if config.single_leg_mode {
+            format!(
+                "YES {:.4} + NO {:.4} = {:.4} within {}s of close (single-leg, cheaper side)",
+                yes_price, no_price, combined, seconds_to_close
             )
         } else {
             format!(
@@ -165,91 +573,353 @@ pub fn pick_opportunities(
                 yes_price, no_price, combined, seconds_to_close
             )
         };
+        if !qualifies_fast && !signal_trade && metric_name != "combined" {
+            reason.push_str(&format!(
+                " | {} {:.4} < {:.4}",
+                metric_name, metric_value, metric_limit
+            ));
+        }
         if let Some(signal) = &lag_signal {
             reason.push_str(&format!(
-                " | CEX lag {} {} strike {:.2}: model_yes {:.3} vs kalshi_yes {:.3} (lag {:.3})",
+                " | CEX lag {} {} strike {:.2} ({:.1}bps away): model_yes {:.3} vs kalshi_yes {:.3} (lag {:.3})",
                 signal.asset,
                 signal.direction,
                 signal.strike,
+                signal.strike_distance_bps,
                 signal.model_yes_prob,
                 signal.kalshi_yes_prob,
                 signal.lag
             ));
+            let model_combined = signal.model_yes_prob + signal.model_no_prob;
+            reason.push_str(&format!(
+                " | model_combined {:.4} vs market {:.4}",
+                model_combined, combined
+            ));
+            if combined - model_combined > config.model_combined_edge {
+                reason.push_str(" (model edge)");
+            }
+        }
+
+        let verdict_reason = if final_basket {
+            "final-basket"
+        } else if qualifies_fast {
+            "fast-band"
+        } else if signal_trade {
+            "signal-only"
+        } else if config.single_leg_mode {
+            "single-leg"
+        } else {
+            "combined-below-threshold"
+        };
+        if config.log_decisions {
+            log_err!(
+                "{}",
+                verdict_line(
+                    &market,
+                    "QUALIFY",
+                    verdict_reason,
+                    seconds_to_close,
+                    lag_signal.as_ref()
+                )
+            );
         }
 
         decisions.push(Decision {
             market,
+            close_time,
             orders,
             reason,
+            combined,
+            lag_signal: lag_signal.clone(),
+            fast: qualifies_fast,
+            verdict: verdict_reason,
         });
 
         if config.log_decisions {
             if qualifies_fast {
                 log_err!(
-                    "  -> QUALIFY: ttl {}s with YES {:.4} / NO {:.4} in 0.90-0.97 band",
+                    "  -> QUALIFY: ttl {}s with YES {:.4} / NO {:.4} in {:.2}-{:.2} band",
                     seconds_to_close,
                     yes_price,
-                    no_price
+                    no_price,
+                    band_low,
+                    band_high
+                );
+            } else if signal_trade {
+                log_err!(
+                    "  -> QUALIFY: signal-only, {} {:.4} >= {:.4}, seconds_to_close={}",
+                    metric_name,
+                    metric_value,
+                    metric_limit,
+                    seconds_to_close
                 );
             } else {
                 log_err!(
-                    "  -> QUALIFY: combined {:.4} < {:.4}, seconds_to_close={}",
-                    combined,
-                    config.combined_max_price,
+                    "  -> QUALIFY: {} {:.4} < {:.4}, seconds_to_close={}",
+                    metric_name,
+                    metric_value,
+                    metric_limit,
                     seconds_to_close
                 );
             }
             if let Some(signal) = &lag_signal {
                 log_err!(
-                    "  -> CEX LAG: {} {} strike {:.2} ref {:.2} model_yes {:.3} kalshi_yes {:.3} lag {:.3}",
+                    "  -> CEX LAG: {} {} strike {:.2} ref {:.2} (raw {:.2}) model_yes {:.3} kalshi_yes {:.3} lag {:.3}",
                     signal.asset,
                     signal.direction,
                     signal.strike,
                     signal.reference_price,
+                    signal.raw_reference_price,
                     signal.model_yes_prob,
                     signal.kalshi_yes_prob,
                     signal.lag
                 );
+                log_err!(
+                    "  -> MODEL: model_yes {:.3} model_no {:.3} model_combined {:.4} vs market combined {:.4}",
+                    signal.model_yes_prob,
+                    signal.model_no_prob,
+                    signal.model_yes_prob + signal.model_no_prob,
+                    combined
+                );
             }
         }
     }
 
+    if !skips.is_empty() {
+        let mut counts = skips.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let summary = counts
+            .iter()
+            .map(|(reason, count)| format!("{}={}", reason, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        log_err!("Skip summary: {}", summary);
+    }
+
+    decisions.sort_by(|a, b| by_priority(config, a, b));
+    if config.max_decisions > 0 {
+        decisions.truncate(config.max_decisions);
+        for decision in decisions.iter_mut() {
+            let priority = format!(
+                " | priority abs_lag {:.3} combined {:.4}",
+                decision_abs_lag(decision),
+                decision.combined
+            );
+            decision.reason.push_str(&priority);
+        }
+    }
+
     decisions
 }
 
-fn matches_interval(market: &Market, interval_re: &Regex) -> bool {
-    if interval_re.is_match(&market.title) {
-        return true;
+fn record_skip(
+    config: &Config,
+    skips: &mut HashMap<&'static str, usize>,
+    market: &Market,
+    reason: &'static str,
+    seconds_to_close: i64,
+    lag_signal: Option<&LagSignal>,
+) {
+    *skips.entry(reason).or_default() += 1;
+    if config.log_decisions {
+        log_err!(
+            "{}",
+            verdict_line(market, "SKIP", reason, seconds_to_close, lag_signal)
+        );
     }
-    if let Some(subtitle) = &market.subtitle {
-        if interval_re.is_match(subtitle) {
-            return true;
-        }
+}
+
+fn verdict_line(
+    market: &Market,
+    verdict: &str,
+    reason: &str,
+    seconds_to_close: i64,
+    lag_signal: Option<&LagSignal>,
+) -> String {
+    let fmt = |value: Option<f64>, digits: usize| {
+        value
+            .map(|v| format!("{:.*}", digits, v))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let yes = market.yes_ask_price();
+    let no = market.no_ask_price();
+    let combined = yes.zip(no).map(|(y, n)| y + n);
+    let ttl = market
+        .close_time
+        .map(|_| seconds_to_close.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        "VERDICT {} {} reason={} ttl={} yes={} no={} combined={} model_yes={} lag={}",
+        market.ticker,
+        verdict,
+        reason,
+        ttl,
+        fmt(yes, 4),
+        fmt(no, 4),
+        fmt(combined, 4),
+        fmt(lag_signal.map(|s| s.model_yes_prob), 3),
+        fmt(lag_signal.map(|s| s.lag), 3)
+    )
+}
+
+/// Validates every leg of a decision, rounding limit prices in place. Any
+/// invalid leg fails the whole decision so a basket is never placed partially.
+pub fn validate_decision(config: &Config, decision: &mut Decision) -> Result<()> {
+    let market = &decision.market;
+    let errors = decision
+        .orders
+        .iter_mut()
+        .filter_map(|order| {
+            let err = validate_order(config, order, market).err()?;
+            Some(format!(
+                "{:?} {} @ {:.2}: {}",
+                order.side, order.quantity, order.price_dollars, err
+            ))
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} of {} legs invalid: {}",
+        errors.len(),
+        decision.orders.len(),
+        errors.join("; ")
+    ))
+}
+
+fn validate_order(config: &Config, order: &mut OrderRequest, market: &Market) -> Result<()> {
+    if order.ticker != market.ticker {
+        return Err(anyhow!(
+            "order ticker {} does not match market {}",
+            order.ticker,
+            market.ticker
+        ));
     }
-    if let Some(event_ticker) = &market.event_ticker {
-        if interval_re.is_match(event_ticker) {
-            return true;
+    if order.quantity < config.min_quantity {
+        return Err(anyhow!(
+            "quantity {} below minimum {}",
+            order.quantity,
+            config.min_quantity
+        ));
+    }
+    if matches!(order.order_type, OrderType::Limit) {
+        let rounded = round_price(order.price_dollars, config.price_decimals);
+        if !(0.01..=0.99).contains(&rounded) {
+            return Err(anyhow!(
+                "price {:.4} outside valid range 0.01-0.99",
+                order.price_dollars
+            ));
         }
+        order.price_dollars = rounded;
+    }
+    Ok(())
+}
+
+fn is_sane_lag(config: &Config, market: &Market, signal: &LagSignal) -> bool {
+    if signal.abs_lag <= config.cex_lag_max_sane {
+        return true;
+    }
+    if config.log_decisions {
+        log_err!(
+            "  -> WARN: {} lag {:.3} exceeds sane max {:.3} (model_yes {:.3} vs kalshi_yes {:.3}, strike {:.2} {}); ignoring signal",
+            market.ticker,
+            signal.lag,
+            config.cex_lag_max_sane,
+            signal.model_yes_prob,
+            signal.kalshi_yes_prob,
+            signal.strike,
+            signal.direction
+        );
     }
     false
 }
 
-#[derive(Debug, Clone)]
-struct LagSignal {
-    asset: String,
-    direction: Direction,
-    strike: f64,
-    reference_price: f64,
-    model_yes_prob: f64,
-    kalshi_yes_prob: f64,
-    lag: f64,
-    abs_lag: f64,
+fn crosses_position(order: &OrderRequest, position: i64) -> bool {
+    if !matches!(order.action, Action::Buy) {
+        return false;
+    }
+    match order.side {
+        Side::Yes => position < 0,
+        Side::No => position > 0,
+    }
+}
+
+fn combined_metric(
+    config: &Config,
+    combined: f64,
+    seconds_to_close: i64,
+) -> (&'static str, f64, f64) {
+    let horizon = config.combined_ttl_horizon_secs.max(1) as f64;
+    let ttl = (seconds_to_close as f64).clamp(0.0, horizon);
+    let tightening = config.combined_ttl_tightening * (1.0 - ttl / horizon);
+    match config.combined_metric.as_str() {
+        "gap" => ("gap", combined - 1.0, config.max_gap - tightening),
+        _ => ("combined", combined, config.combined_max_price - tightening),
+    }
+}
+
+fn by_priority(config: &Config, a: &Decision, b: &Decision) -> std::cmp::Ordering {
+    let order = decision_abs_lag(b)
+        .total_cmp(&decision_abs_lag(a))
+        .then(a.combined.total_cmp(&b.combined));
+    if config.shuffle_markets {
+        return order;
+    }
+    order.then_with(|| a.market.ticker.cmp(&b.market.ticker))
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
+fn decision_abs_lag(decision: &Decision) -> f64 {
+    decision
+        .lag_signal
+        .as_ref()
+        .map(|signal| signal.abs_lag)
+        .unwrap_or(0.0)
+}
+
+fn improve_price(ask: f64, cents: i64) -> f64 {
+    let improved = ask + cents as f64 / 100.0;
+    improved.min(0.99).max(ask)
+}
+
+fn matches_interval(
+    market: &Market,
+    interval_re: &Regex,
+    fields: &[String],
+) -> Option<&'static str> {
+    let candidates = [
+        ("title", Some(market.title.as_str())),
+        ("subtitle", market.subtitle.as_deref()),
+        ("event_ticker", market.event_ticker.as_deref()),
+    ];
+    candidates
+        .into_iter()
+        .filter(|(name, _)| fields.iter().any(|f| f == name))
+        .find(|(_, value)| value.is_some_and(|v| interval_re.is_match(v)))
+        .map(|(name, _)| name)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LagSignal {
+    pub asset: String,
+    pub direction: Direction,
+    pub strike: f64,
+    pub raw_reference_price: f64,
+    pub reference_price: f64,
+    pub model_yes_prob: f64,
+    pub model_no_prob: f64,
+    pub kalshi_yes_prob: f64,
+    pub lag: f64,
+    pub abs_lag: f64,
+    pub strike_distance_bps: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
     Above,
     Below,
+    Equal,
 }
 
 impl std::fmt::Display for Direction {
@@ -257,6 +927,7 @@ impl std::fmt::Display for Direction {
         match self {
             Direction::Above => write!(f, "above"),
             Direction::Below => write!(f, "below"),
+            Direction::Equal => write!(f, "equal"),
         }
     }
 }
@@ -267,45 +938,175 @@ fn compute_cex_lag_signal(
     kalshi_yes_prob: f64,
     cex_refs: Option<&HashMap<String, AssetReference>>,
 ) -> Option<LagSignal> {
+    let explain_enabled = config.explain_ticker.as_deref() == Some(market.ticker.as_str());
+    macro_rules! explain {
+        ($($arg:tt)*) => {
+            if explain_enabled {
+                log_err!("EXPLAIN {}: {}", market.ticker, format!($($arg)*));
+            }
+        };
+    }
+    explain!(
+        "title='{}' subtitle='{}' yes_sub_title='{}' no_sub_title='{}' yes={} no={}",
+        market.title,
+        market.subtitle.as_deref().unwrap_or_default(),
+        market.yes_sub_title.as_deref().unwrap_or_default(),
+        market.no_sub_title.as_deref().unwrap_or_default(),
+        market
+            .yes_ask_price()
+            .map(|p| format!("{:.4}", p))
+            .unwrap_or_else(|| "-".to_string()),
+        market
+            .no_ask_price()
+            .map(|p| format!("{:.4}", p))
+            .unwrap_or_else(|| "-".to_string())
+    );
+
     if !config.enable_cex_lag_scan {
+        explain!("no signal: ENABLE_CEX_LAG_SCAN is off");
         return None;
     }
-    let refs = cex_refs?;
-    let asset = market.primary_asset()?;
-    if asset != "BTC" && asset != "ETH" {
+    let Some(refs) = cex_refs else {
+        explain!("no signal: no CEX references this run");
         return None;
-    }
-    let reference = refs.get(asset)?;
+    };
+    let Some(asset) = market
+        .primary_asset()
+        .filter(|a| *a == "BTC" || *a == "ETH")
+    else {
+        explain!(
+            "no signal: asset {:?} is not BTC/ETH",
+            market.primary_asset()
+        );
+        return None;
+    };
+    let Some(reference) = refs.get(asset) else {
+        explain!("no signal: no {} reference", asset);
+        return None;
+    };
+    explain!(
+        "{} reference {:.2} from {} venues, confidence {:.2}",
+        asset,
+        reference.reference_price,
+        reference.quotes.len(),
+        reference.confidence
+    );
     if reference.quotes.len() < config.cex_lag_min_sources {
+        explain!(
+            "no signal: {} venues < CEX_LAG_MIN_SOURCES {}",
+            reference.quotes.len(),
+            config.cex_lag_min_sources
+        );
+        return None;
+    }
+    if reference.confidence < config.cex_min_confidence {
+        explain!(
+            "no signal: confidence {:.2} < CEX_MIN_CONFIDENCE {:.2}",
+            reference.confidence,
+            config.cex_min_confidence
+        );
         return None;
     }
 
-    let direction = parse_direction(&market.title, market.subtitle.as_deref())?;
-    let strike = parse_strike(&market.title, market.subtitle.as_deref())?;
-    if strike <= 0.0 {
+    let direction = parse_direction(market);
+    let strike = parse_strike(market);
+    explain!("parsed direction={:?} strike={:?}", direction, strike);
+    let direction = direction?;
+    if direction == Direction::Equal && config.equal_market_prob <= 0.0 {
+        explain!("no signal: equality market and EQUAL_MARKET_PROB <= 0");
+        return None;
+    }
+    let strike = strike.filter(|s| *s > 0.0)?;
+    let strike_distance_pct =
+        ((strike - reference.reference_price) / reference.reference_price).abs() * 100.0;
+    if strike_distance_pct > config.strike_sanity_pct {
+        log_err!(
+            "  -> WARN: {} strike {:.2} is {:.1}% from {} ref {:.2} (> {:.1}%); ignoring signal",
+            market.ticker,
+            strike,
+            strike_distance_pct,
+            asset,
+            reference.reference_price,
+            config.strike_sanity_pct
+        );
         return None;
     }
 
-    let model_yes_prob = model_yes_probability(asset, reference.reference_price, strike, direction);
+    let bias_bps = config.reference_bias_bps.get(asset).copied().unwrap_or(0.0);
+    let reference_price = reference.reference_price * (1.0 + bias_bps / 10_000.0);
+    if explain_enabled {
+        let scale = scale_bps(asset);
+        let dist_bps = ((reference_price - strike) / strike) * 10_000.0;
+        explain!(
+            "bias {:.1}bps -> reference {:.2}; dist_bps {:.2} / scale_bps {:.1} = {:.4}; sigmoid -> P(above) {:.6}",
+            bias_bps,
+            reference_price,
+            dist_bps,
+            scale,
+            dist_bps / scale,
+            sigmoid(dist_bps / scale)
+        );
+    }
+    let model_yes_prob = model_yes_probability(
+        asset,
+        reference_price,
+        strike,
+        direction,
+        config.equal_market_prob,
+        config.prob_clamp,
+    );
     let lag = model_yes_prob - kalshi_yes_prob;
+    if explain_enabled {
+        let raw = model_yes_probability(
+            asset,
+            reference_price,
+            strike,
+            direction,
+            config.equal_market_prob,
+            0.0,
+        );
+        let clamped = if raw != model_yes_prob {
+            format!(
+                " (clamped from {:.6} by PROB_CLAMP {})",
+                raw, config.prob_clamp
+            )
+        } else {
+            String::new()
+        };
+        explain!(
+            "model_yes {:.6}{} vs kalshi_yes {:.4} -> lag {:+.4} (threshold {:.4})",
+            model_yes_prob,
+            clamped,
+            kalshi_yes_prob,
+            lag,
+            config.cex_lag_threshold
+        );
+    }
     Some(LagSignal {
         asset: asset.to_string(),
         direction,
         strike,
-        reference_price: reference.reference_price,
+        raw_reference_price: reference.reference_price,
+        reference_price,
         model_yes_prob,
+        model_no_prob: 1.0 - model_yes_prob,
         kalshi_yes_prob,
         lag,
         abs_lag: lag.abs(),
+        strike_distance_bps: ((reference_price - strike) / strike).abs() * 10_000.0,
     })
 }
 
-fn parse_direction(title: &str, subtitle: Option<&str>) -> Option<Direction> {
-    let mut text = title.to_lowercase();
-    if let Some(sub) = subtitle {
+fn parse_direction(market: &Market) -> Option<Direction> {
+    let mut text = market.title.to_lowercase();
+    for extra in [&market.subtitle, &market.yes_sub_title]
+        .into_iter()
+        .flatten()
+    {
         text.push(' ');
-        text.push_str(&sub.to_lowercase());
+        text.push_str(&extra.to_lowercase());
     }
+    text.push(' ');
 
     let above_terms = [
         " at or above ",
@@ -321,6 +1122,12 @@ fn parse_direction(title: &str, subtitle: Option<&str>) -> Option<Direction> {
         " less than ",
         " lower than ",
     ];
+    let equal_terms = [
+        " exactly ",
+        " to the dollar",
+        " equal to ",
+        " precisely at ",
+    ];
 
     if above_terms.iter().any(|term| text.contains(term)) {
         return Some(Direction::Above);
@@ -328,31 +1135,37 @@ fn parse_direction(title: &str, subtitle: Option<&str>) -> Option<Direction> {
     if below_terms.iter().any(|term| text.contains(term)) {
         return Some(Direction::Below);
     }
+    if equal_terms.iter().any(|term| text.contains(term)) {
+        return Some(Direction::Equal);
+    }
     None
 }
 
-fn parse_strike(title: &str, subtitle: Option<&str>) -> Option<f64> {
-    let number_re = Regex::new(r"\$?\d{1,3}(?:,\d{3})*(?:\.\d+)?").ok()?;
-    let mut candidates = Vec::new();
-
-    for cap in number_re.find_iter(title) {
-        if let Ok(value) = parse_number_fragment(cap.as_str()) {
-            candidates.push(value);
-        }
-    }
-
-    if let Some(sub) = subtitle {
-        for cap in number_re.find_iter(sub) {
-            if let Ok(value) = parse_number_fragment(cap.as_str()) {
-                candidates.push(value);
-            }
-        }
-    }
+fn parse_strike(market: &Market) -> Option<f64> {
+    let number_re = Regex::new(r"\$?\d+(?:,\d{3})*(?:\.\d+)?").ok()?;
+    let candidates = |texts: &[Option<&str>], dollars_only: bool| {
+        texts
+            .iter()
+            .flatten()
+            .flat_map(|text| number_re.find_iter(text))
+            .filter(|cap| !dollars_only || cap.as_str().starts_with('$'))
+            .filter_map(|cap| parse_number_fragment(cap.as_str()).ok())
+            .filter(|v| *v >= 100.0)
+            .max_by(|a, b| a.total_cmp(b))
+    };
 
-    candidates
-        .into_iter()
-        .filter(|v| *v >= 100.0)
-        .max_by(|a, b| a.total_cmp(b))
+    // Dollar amounts win over bare numbers so a date in a generic title
+    // ("Jan 5, 2026") cannot shadow the strike in an outcome subtitle.
+    let primary = [
+        Some(market.title.as_str()),
+        market.subtitle.as_deref(),
+        market.yes_sub_title.as_deref(),
+    ];
+    let fallback = [market.no_sub_title.as_deref()];
+    candidates(&primary, true)
+        .or_else(|| candidates(&fallback, true))
+        .or_else(|| candidates(&primary, false))
+        .or_else(|| candidates(&fallback, false))
 }
 
 fn parse_number_fragment(fragment: &str) -> Result<f64, std::num::ParseFloatError> {
@@ -364,17 +1177,27 @@ fn model_yes_probability(
     reference_price: f64,
     strike: f64,
     direction: Direction,
+    equal_prob: f64,
+    prob_clamp: f64,
 ) -> f64 {
-    let scale_bps = match asset {
-        "BTC" => 45.0,
-        "ETH" => 65.0,
-        _ => 55.0,
-    };
     let dist_bps = ((reference_price - strike) / strike) * 10_000.0;
-    let above_prob = sigmoid(dist_bps / scale_bps);
-    match direction {
+    let above_prob = sigmoid(dist_bps / scale_bps(asset));
+    let prob = match direction {
         Direction::Above => above_prob,
         Direction::Below => 1.0 - above_prob,
+        Direction::Equal => equal_prob,
+    };
+    // Far from the strike the sigmoid saturates to exactly 0.0 or 1.0, which
+    // no market price can justify and which breaks odds/EV math downstream.
+    let eps = prob_clamp.clamp(0.0, 0.5);
+    prob.clamp(eps, 1.0 - eps)
+}
+
+fn scale_bps(asset: &str) -> f64 {
+    match asset {
+        "BTC" => 45.0,
+        "ETH" => 65.0,
+        _ => 55.0,
     }
 }
 
@@ -387,3 +1210,428 @@ fn sigmoid(x: f64) -> f64 {
         z / (1.0 + z)
     }
 }
+
+/// Limits a decision re-evaluated after a submit wait to what the order caps
+/// already accepted: the same sides, at no more than the original quantities.
+pub fn clip_refreshed(original: &Decision, mut refreshed: Decision) -> Result<Decision> {
+    if refreshed.orders.len() > original.orders.len() {
+        return Err(anyhow!(
+            "refreshed decision grew from {} to {} orders",
+            original.orders.len(),
+            refreshed.orders.len()
+        ));
+    }
+    for order in &mut refreshed.orders {
+        let Some(accepted) = original.orders.iter().find(|o| o.side == order.side) else {
+            return Err(anyhow!("refreshed decision adds a {:?} leg", order.side));
+        };
+        order.quantity = order.quantity.min(accepted.quantity);
+    }
+    Ok(refreshed)
+}
+
+/// Caps orders per market and per run. Decisions are ranked by priority
+/// (descending abs_lag, then ascending combined) before the run cap is
+/// applied, so the lowest-priority decisions are the ones dropped. A decision
+/// is kept or dropped whole so a YES+NO basket never loses a leg.
+pub fn apply_order_limits(config: &Config, mut decisions: Vec<Decision>) -> (Vec<Decision>, usize) {
+    decisions.sort_by(|a, b| by_priority(config, a, b));
+    let mut kept = Vec::new();
+    let mut total = 0usize;
+    let mut dropped = 0usize;
+
+    for decision in decisions {
+        let orders = decision.orders.len();
+        let remaining = config.max_orders_per_run.saturating_sub(total);
+        if orders > config.max_orders_per_market || orders > remaining {
+            dropped += orders;
+            continue;
+        }
+        if orders == 0 {
+            continue;
+        }
+        total += orders;
+        kept.push(decision);
+    }
+
+    (kept, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 5, 14, 0, 0).unwrap()
+    }
+
+    fn test_config() -> Config {
+        let mut config = Config::defaults();
+        config.enable_cex_lag_scan = false;
+        config.trade_mode = TradeMode::Aggressive;
+        config.combined_max_price = 1.0;
+        config.min_seconds_to_close = 3;
+        config.allowed_statuses = vec!["open".to_string(), "active".to_string()];
+        config
+    }
+
+    fn market(fields: serde_json::Value) -> Market {
+        let mut payload = serde_json::json!({
+            "ticker": "KXBTC15M-26JAN051415-15",
+            "title": "BTC price up in next 15 mins?",
+            "event_ticker": "KXBTC15M-26JAN051415",
+            "status": "open",
+            "close_time": "2026-01-05T14:10:00Z",
+        });
+        for (key, value) in fields.as_object().unwrap() {
+            payload[key] = value.clone();
+        }
+        serde_json::from_value(payload).unwrap()
+    }
+
+    fn pick(config: &Config, markets: Vec<Market>) -> Vec<Decision> {
+        pick_opportunities(config, now(), markets, None, &HashMap::new())
+    }
+
+    #[test]
+    fn cents_only_market_qualifies() {
+        let market = market(serde_json::json!({ "yes_ask": 45, "no_ask": 50 }));
+        assert_eq!(market.yes_ask_price(), Some(0.45));
+        assert_eq!(market.no_ask_price(), Some(0.50));
+
+        let decisions = pick(&test_config(), vec![market]);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].market.ticker, "KXBTC15M-26JAN051415-15");
+        assert!((decisions[0].combined - 0.95).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_now_gives_stable_seconds_to_close() {
+        use crate::client::{KalshiClient, MockClient};
+
+        let mut config = test_config();
+        config.dry_run = true;
+        config.fixed_now_rfc3339 = Some(now());
+        let client = MockClient::new(config.clone());
+        for _ in 0..2 {
+            let market = market(serde_json::json!({
+                "yes_ask_dollars": "0.45",
+                "no_ask_dollars": "0.50",
+            }));
+            let decisions =
+                pick_opportunities(&config, client.now(), vec![market], None, &HashMap::new());
+            assert_eq!(decisions.len(), 1);
+            assert!(
+                decisions[0].reason.contains("within 600s of close"),
+                "{}",
+                decisions[0].reason
+            );
+        }
+
+        config.dry_run = false;
+        assert_eq!(config.fixed_now(), None);
+    }
+
+    #[test]
+    fn signal_only_never_trades_without_a_signal() {
+        let mut config = test_config();
+        config.trade_mode = TradeMode::SignalOnly;
+        let market = market(serde_json::json!({
+            "yes_ask_dollars": "0.45",
+            "no_ask_dollars": "0.50",
+        }));
+        assert!(pick(&config, vec![market.clone()]).is_empty());
+
+        config.trade_mode = TradeMode::Aggressive;
+        assert_eq!(pick(&config, vec![market]).len(), 1);
+    }
+
+    fn lag_signal(lag: f64) -> LagSignal {
+        LagSignal {
+            asset: "BTC".to_string(),
+            direction: Direction::Above,
+            strike: 97_000.0,
+            raw_reference_price: 97_100.0,
+            reference_price: 97_100.0,
+            model_yes_prob: 0.5 + lag,
+            model_no_prob: 0.5 - lag,
+            kalshi_yes_prob: 0.5,
+            lag,
+            abs_lag: lag.abs(),
+            strike_distance_bps: 10.3,
+        }
+    }
+
+    #[test]
+    fn sane_lag_boundary() {
+        let mut config = test_config();
+        config.cex_lag_max_sane = 0.25;
+        let market = market(serde_json::json!({}));
+        assert!(is_sane_lag(&config, &market, &lag_signal(0.0)));
+        assert!(is_sane_lag(&config, &market, &lag_signal(0.25)));
+        assert!(is_sane_lag(&config, &market, &lag_signal(-0.25)));
+        assert!(!is_sane_lag(&config, &market, &lag_signal(0.2501)));
+        assert!(!is_sane_lag(&config, &market, &lag_signal(-0.2501)));
+    }
+
+    #[test]
+    fn shuffle_breaks_priority_ties_by_scan_order() {
+        let tied = |ticker: &str| {
+            market(serde_json::json!({
+                "ticker": ticker,
+                "yes_ask_dollars": "0.45",
+                "no_ask_dollars": "0.50",
+            }))
+        };
+        let tickers = |decisions: Vec<Decision>| {
+            decisions
+                .into_iter()
+                .map(|d| d.market.ticker)
+                .collect::<Vec<_>>()
+        };
+        let mut config = test_config();
+        let markets = vec![tied("KXBTC15M-C"), tied("KXBTC15M-A"), tied("KXBTC15M-B")];
+
+        config.shuffle_markets = false;
+        assert_eq!(
+            tickers(pick(&config, markets.clone())),
+            ["KXBTC15M-A", "KXBTC15M-B", "KXBTC15M-C"]
+        );
+        config.shuffle_markets = true;
+        assert_eq!(
+            tickers(pick(&config, markets)),
+            ["KXBTC15M-C", "KXBTC15M-A", "KXBTC15M-B"]
+        );
+    }
+
+    #[test]
+    fn order_caps_drop_whole_baskets() {
+        let basket = |ticker: &str, yes: &str| {
+            market(serde_json::json!({
+                "ticker": ticker,
+                "yes_ask_dollars": yes,
+                "no_ask_dollars": "0.50",
+            }))
+        };
+        let mut config = test_config();
+        let markets = vec![
+            basket("KXBTC15M-A", "0.40"),
+            basket("KXBTC15M-B", "0.42"),
+            basket("KXBTC15M-C", "0.44"),
+        ];
+        let decisions = pick(&config, markets);
+        assert!(decisions.iter().all(|d| d.orders.len() == 2));
+
+        config.max_orders_per_market = 2;
+        config.max_orders_per_run = 5;
+        let (kept, dropped) = apply_order_limits(&config, decisions.clone());
+        let kept = kept
+            .iter()
+            .map(|d| (d.market.ticker.as_str(), d.orders.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [("KXBTC15M-A", 2), ("KXBTC15M-B", 2)]);
+        assert_eq!(dropped, 2);
+
+        config.max_orders_per_market = 1;
+        config.max_orders_per_run = 20;
+        let (kept, dropped) = apply_order_limits(&config, decisions);
+        assert!(kept.is_empty());
+        assert_eq!(dropped, 6);
+    }
+
+    #[test]
+    fn validation_rounds_to_price_decimals() {
+        let mut config = test_config();
+        config.price_improve_cents = 0;
+        let markets = vec![market(serde_json::json!({
+            "yes_ask_dollars": "0.4567",
+            "no_ask_dollars": "0.5012",
+        }))];
+        let decision = pick(&config, markets).remove(0);
+        let prices = |config: &Config| {
+            let mut decision = decision.clone();
+            validate_decision(config, &mut decision).unwrap();
+            decision
+                .orders
+                .iter()
+                .map(|o| o.price_dollars)
+                .collect::<Vec<_>>()
+        };
+
+        config.price_decimals = 2;
+        assert_eq!(prices(&config), [0.46, 0.50]);
+        config.price_decimals = 4;
+        assert_eq!(prices(&config), [0.4567, 0.5012]);
+    }
+
+    #[test]
+    fn refreshed_decision_is_clipped_to_the_original() {
+        let config = test_config();
+        let fast = pick(
+            &config,
+            vec![market(serde_json::json!({
+                "close_time": "2026-01-05T14:00:30Z",
+                "yes_ask_dollars": "0.93",
+                "no_ask_dollars": "0.09",
+            }))],
+        )
+        .remove(0);
+        let basket = pick(
+            &config,
+            vec![market(serde_json::json!({
+                "yes_ask_dollars": "0.45",
+                "no_ask_dollars": "0.50",
+            }))],
+        )
+        .remove(0);
+        assert_eq!(fast.orders.len(), 1);
+        assert_eq!(basket.orders.len(), 2);
+
+        assert!(clip_refreshed(&fast, basket.clone()).is_err());
+
+        let mut bigger = fast.clone();
+        bigger.orders[0].quantity += 5;
+        let clipped = clip_refreshed(&fast, bigger).unwrap();
+        assert_eq!(clipped.orders[0].quantity, fast.orders[0].quantity);
+
+        let mut no_leg = basket.clone();
+        no_leg.orders.retain(|o| o.side == Side::No);
+        assert!(clip_refreshed(&fast, no_leg.clone()).is_err());
+        assert_eq!(clip_refreshed(&basket, no_leg).unwrap().orders.len(), 1);
+    }
+
+    fn btc_reference(price: f64) -> HashMap<String, AssetReference> {
+        let quotes = ["coinbase", "kraken", "binance"]
+            .into_iter()
+            .map(|venue| crate::cex::VenueQuote {
+                venue: venue.to_string(),
+                mid: price,
+                spread_pct: 0.01,
+                observed_at: now(),
+            })
+            .collect();
+        let reference = AssetReference {
+            asset: "BTC".to_string(),
+            reference_price: price,
+            quotes,
+            confidence: 1.0,
+        };
+        HashMap::from([("BTC".to_string(), reference)])
+    }
+
+    fn signal_config() -> Config {
+        let mut config = test_config();
+        config.enable_cex_lag_scan = true;
+        config.cex_lag_min_sources = 2;
+        config.cex_min_confidence = 0.0;
+        config.strike_sanity_pct = 50.0;
+        config.reference_bias_bps.clear();
+        config.explain_ticker = None;
+        config
+    }
+
+    #[test]
+    fn decoy_giant_strike_is_ignored() {
+        let config = signal_config();
+        let refs = btc_reference(97_100.0);
+
+        let decoy = market(serde_json::json!({
+            "title": "Bitcoin above $999,999,999 at 2:15pm? (15 min)",
+        }));
+        assert!(compute_cex_lag_signal(&config, &decoy, 0.5, Some(&refs)).is_none());
+
+        let real = market(serde_json::json!({
+            "title": "Bitcoin above $97,000 at 2:15pm? (15 min)",
+        }));
+        let signal = compute_cex_lag_signal(&config, &real, 0.5, Some(&refs)).unwrap();
+        assert_eq!(signal.strike, 97_000.0);
+        assert_eq!(signal.direction, Direction::Above);
+        assert!(signal.model_yes_prob > 0.5);
+    }
+
+    #[test]
+    fn outcome_subtitles_supply_direction_and_strike() {
+        let above = market(serde_json::json!({
+            "ticker": "KXBTCD-26JAN0514-T97249.99",
+            "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+            "yes_sub_title": "$97,250 or above",
+            "no_sub_title": "$97,250 or above",
+        }));
+        assert_eq!(parse_direction(&above), Some(Direction::Above));
+        assert_eq!(parse_strike(&above), Some(97_250.0));
+
+        let below = market(serde_json::json!({
+            "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+            "yes_sub_title": "$96,999.99 or below",
+        }));
+        assert_eq!(parse_direction(&below), Some(Direction::Below));
+        assert_eq!(parse_strike(&below), Some(96_999.99));
+
+        let no_only = market(serde_json::json!({
+            "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+            "yes_sub_title": "Yes",
+            "no_sub_title": "$97,250 or above",
+        }));
+        assert_eq!(parse_direction(&no_only), None);
+        assert_eq!(parse_strike(&no_only), Some(97_250.0));
+
+        let generic = market(serde_json::json!({
+            "title": "Bitcoin price on Jan 5, 2026 at 2pm EST?",
+            "yes_sub_title": "Yes",
+            "no_sub_title": "No",
+        }));
+        assert_eq!(parse_direction(&generic), None);
+        assert!(compute_cex_lag_signal(
+            &signal_config(),
+            &generic,
+            0.5,
+            Some(&btc_reference(97_100.0))
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn extreme_reference_ratios_are_clamped() {
+        let clamp = 1e-4;
+        let far_above =
+            model_yes_probability("BTC", 970_000.0, 97_000.0, Direction::Above, 0.0, clamp);
+        assert_eq!(far_above, 1.0 - clamp);
+        let far_below =
+            model_yes_probability("BTC", 9_700.0, 97_000.0, Direction::Above, 0.0, clamp);
+        assert_eq!(far_below, clamp);
+        let inverted =
+            model_yes_probability("BTC", 970_000.0, 97_000.0, Direction::Below, 0.0, clamp);
+        assert_eq!(inverted, clamp);
+
+        let near = model_yes_probability("BTC", 97_050.0, 97_000.0, Direction::Above, 0.0, clamp);
+        assert!(near > 0.5 && near < 1.0 - clamp);
+
+        let unclamped =
+            model_yes_probability("BTC", 970_000.0, 97_000.0, Direction::Above, 0.0, 0.0);
+        assert_eq!(unclamped, 1.0);
+        let oversized =
+            model_yes_probability("BTC", 970_000.0, 97_000.0, Direction::Above, 0.0, 0.9);
+        assert_eq!(oversized, 0.5);
+    }
+
+    #[test]
+    fn settled_and_closed_markets_are_skipped() {
+        let config = test_config();
+        for status in ["settled", "closed", "SETTLED"] {
+            let market = market(serde_json::json!({
+                "status": status,
+                "yes_ask_dollars": "0.45",
+                "no_ask_dollars": "0.50",
+            }));
+            assert!(pick(&config, vec![market]).is_empty(), "{}", status);
+        }
+
+        let open = market(serde_json::json!({
+            "status": "active",
+            "yes_ask_dollars": "0.45",
+            "no_ask_dollars": "0.50",
+        }));
+        assert_eq!(pick(&config, vec![open]).len(), 1);
+    }
+}
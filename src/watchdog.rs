@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::Client;
+
+use crate::log_err;
+use crate::slack;
+
+struct WatchdogState {
+    started: Option<Instant>,
+    alerted: bool,
+}
+
+pub struct Watchdog {
+    state: Arc<Mutex<WatchdogState>>,
+}
+
+impl Watchdog {
+    pub fn spawn(limit_secs: u64, abort: bool, webhook_url: Option<String>, http: Client) -> Self {
+        let state = Arc::new(Mutex::new(WatchdogState {
+            started: None,
+            alerted: false,
+        }));
+        let shared = Arc::clone(&state);
+
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            let Ok(mut guard) = shared.lock() else {
+                return;
+            };
+            let Some(started) = guard.started else {
+                continue;
+            };
+            if guard.alerted || started.elapsed() < Duration::from_secs(limit_secs) {
+                continue;
+            }
+            guard.alerted = true;
+            drop(guard);
+
+            let message = format!("Watchdog: run exceeded {} seconds", limit_secs);
+            log_err!("{}", message);
+            if let Some(url) = &webhook_url {
+                if let Err(err) = slack::post_run_log(&http, url, &message, None) {
+                    log_err!("Watchdog Slack post failed: {}", err);
+                }
+            }
+            if abort {
+                std::process::exit(2);
+            }
+        });
+
+        Self { state }
+    }
+
+    pub fn reset(&self) {
+        if let Ok(mut guard) = self.state.lock() {
+            guard.started = Some(Instant::now());
+            guard.alerted = false;
+        }
+    }
+
+    pub fn finish(&self) {
+        if let Ok(mut guard) = self.state.lock() {
+            guard.started = None;
+        }
+    }
+}